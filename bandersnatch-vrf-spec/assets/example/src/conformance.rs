@@ -0,0 +1,178 @@
+//! Safrole/JAM bandersnatch test-vector conformance harness.
+//!
+//! Ingests the published JAM/Safrole test vectors (JSON files with a
+//! `ring_set`, `eta2_prime` entropy, per-attempt VRF inputs, and the expected
+//! `expected_vrf_output_hash`/`expected_verified` result) and replays them through the same
+//! [`crate::Prover`]/[`crate::Verifier`] paths the server uses, so this
+//! binary stays spec-conformant across `ark-vrf` upgrades.
+
+use crate::{compose_gamma_z, Prover, Verifier};
+use serde::Deserialize;
+
+/// One `jam_ticket_seal` attempt within a vector: the attempt index and the
+/// expected VRF output hash / acceptance flag after sign-then-verify.
+#[derive(Debug, Deserialize)]
+pub struct VectorAttempt {
+    pub attempt: u8,
+    pub expected_vrf_output_hash: String,
+    pub expected_verified: bool,
+}
+
+/// A single Safrole bandersnatch test vector.
+#[derive(Debug, Deserialize)]
+pub struct SafroleTestVector {
+    pub ring_set: Vec<String>,
+    pub prover_index: usize,
+    pub eta2_prime: String,
+    pub attempts: Vec<VectorAttempt>,
+}
+
+/// Outcome of replaying one [`VectorAttempt`].
+#[derive(Debug)]
+pub struct AttemptReport {
+    pub attempt: u8,
+    pub passed: bool,
+    pub detail: String,
+}
+
+/// Outcome of replaying a whole [`SafroleTestVector`].
+#[derive(Debug)]
+pub struct VectorReport {
+    pub attempts: Vec<AttemptReport>,
+}
+
+impl VectorReport {
+    pub fn all_passed(&self) -> bool {
+        self.attempts.iter().all(|a| a.passed)
+    }
+}
+
+fn ticket_seal_input(eta2_prime: &[u8], attempt: u8) -> Vec<u8> {
+    let mut input = Vec::new();
+    input.extend_from_slice(b"jam_ticket_seal");
+    input.extend_from_slice(eta2_prime);
+    input.push(attempt);
+    input
+}
+
+/// Reconstruct the ring, recompute `gamma_z`, then for each attempt sign
+/// with `Prover::ring_vrf_sign` and verify with `Verifier::ring_vrf_verify`,
+/// comparing the produced output hash and acceptance flag against the
+/// vector's expectations.
+pub fn run_test_vector(vector: &SafroleTestVector) -> VectorReport {
+    use ark_vrf::reexports::ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use bandersnatch::Public;
+
+    let ring: Vec<Public> = vector
+        .ring_set
+        .iter()
+        .map(|hex_str| {
+            let bytes = hex::decode(hex_str.trim_start_matches("0x")).expect("valid ring key hex");
+            Public::deserialize_compressed(&bytes[..]).expect("valid ring key point")
+        })
+        .collect();
+
+    // `compose_gamma_z` is the same commitment derivation `gamma_z` uses
+    // on-chain; comparing it with the verifier's own commitment below
+    // catches drift between the ring reconstruction path and the
+    // prover/verifier setup path.
+    let gamma_z = compose_gamma_z(&vector.ring_set);
+
+    let eta2_prime = hex::decode(vector.eta2_prime.trim_start_matches("0x")).expect("valid eta2_prime hex");
+    let prover = Prover::new(ring.clone(), vector.prover_index);
+    let verifier = Verifier::new(ring);
+    let mut verifier_commitment = Vec::new();
+    verifier.commitment.serialize_compressed(&mut verifier_commitment).expect("serializable ring commitment");
+    let gamma_z_matches = gamma_z == verifier_commitment;
+
+    let attempts = vector
+        .attempts
+        .iter()
+        .map(|attempt_vector| {
+            let vrf_input_data = ticket_seal_input(&eta2_prime, attempt_vector.attempt);
+            let signature = prover.ring_vrf_sign(&vrf_input_data, b"");
+            let result = verifier.ring_vrf_verify(&vrf_input_data, b"", &signature);
+
+            let verified = result.is_ok();
+            let output_hash_matches = match &result {
+                Ok(hash) => hex::encode(hash) == attempt_vector.expected_vrf_output_hash.trim_start_matches("0x"),
+                Err(()) => false,
+            };
+
+            let passed = gamma_z_matches
+                && verified == attempt_vector.expected_verified
+                && (!attempt_vector.expected_verified || output_hash_matches);
+
+            AttemptReport {
+                attempt: attempt_vector.attempt,
+                passed,
+                detail: format!(
+                    "gamma_z_matches={gamma_z_matches}, verified={verified} (expected {}), output_hash_matches={output_hash_matches}",
+                    attempt_vector.expected_verified
+                ),
+            }
+        })
+        .collect();
+
+    VectorReport { attempts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::{fs, path::Path};
+
+    /// Vectors live in `data/safrole_test_vectors/*.json` when available.
+    /// Their distribution isn't vendored into this tree, so an empty/missing
+    /// directory is a pass (nothing to check), not a failure.
+    const VECTOR_DIR: &str = "data/safrole_test_vectors";
+
+    #[test]
+    fn vectors_conform_to_spec() {
+        let dir = Path::new(VECTOR_DIR);
+        if !dir.is_dir() {
+            eprintln!("no test-vector directory at {VECTOR_DIR}, skipping conformance run");
+            return;
+        }
+
+        let mut checked = 0;
+        for entry in fs::read_dir(dir).expect("readable vector directory") {
+            let path = entry.expect("readable dir entry").path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let contents = fs::read_to_string(&path).expect("readable vector file");
+            let vector: SafroleTestVector = serde_json::from_str(&contents).expect("valid vector JSON");
+            let report = run_test_vector(&vector);
+            assert!(report.all_passed(), "{:?} failed: {:?}", path, report.attempts);
+            checked += 1;
+        }
+        eprintln!("checked {checked} Safrole test vector(s)");
+    }
+
+    proptest! {
+        /// Sign then verify with fresh random rings/inputs on every run, to
+        /// catch serialization drift in the `serialize_compressed`/
+        /// `deserialize_compressed` round trip independent of any fixed
+        /// vector file.
+        #[test]
+        fn sign_then_verify_round_trips(seed in 0u64..1000, input in proptest::collection::vec(any::<u8>(), 0..64)) {
+            use bandersnatch::{Public, Secret};
+
+            let mut ring: Vec<Public> = (0..crate::RING_SIZE as u64)
+                .map(|i| Public::from(Secret::from_seed(&(seed + i).to_le_bytes())))
+                .collect();
+            let prover_index = (seed as usize) % ring.len();
+            // `Prover::new` derives its secret from `prover_index` itself, so
+            // the ring member at that position must be that secret's key.
+            ring[prover_index] = Public::from(Secret::from_seed(&prover_index.to_le_bytes()));
+
+            let prover = Prover::new(ring.clone(), prover_index);
+            let verifier = Verifier::new(ring);
+
+            let signature = prover.ring_vrf_sign(&input, b"");
+            prop_assert!(verifier.ring_vrf_verify(&input, b"", &signature).is_ok());
+        }
+    }
+}
@@ -0,0 +1,69 @@
+//! Structured serde views over curve material.
+//!
+//! Every VRF output, proof, and ring commitment in this crate crosses the
+//! API as an opaque `0x...` compressed hex blob, which a consumer can only
+//! inspect by re-running `deserialize_compressed` themselves. [`PointView`]
+//! exposes the decomposed affine `x`/`y` coordinates of a point (the same
+//! pair `print_point` already prints to stdout) alongside its compressed
+//! hex, so a caller that opts out of compression gets a debuggable JSON
+//! object instead.
+
+use ark_vrf::reexports::ark_serialize::CanonicalSerialize;
+use ark_vrf::suites::bandersnatch::{AffinePoint, Public};
+use serde::Serialize;
+
+/// A single affine curve point, as coordinates and as compressed hex.
+#[derive(Serialize)]
+pub struct PointView {
+    pub x: String,
+    pub y: String,
+    pub compressed: String,
+}
+
+impl From<&AffinePoint> for PointView {
+    fn from(point: &AffinePoint) -> Self {
+        let mut buf = Vec::new();
+        point.serialize_compressed(&mut buf).unwrap();
+        Self {
+            x: point.x.to_string(),
+            y: point.y.to_string(),
+            compressed: format!("0x{}", hex::encode(buf)),
+        }
+    }
+}
+
+impl From<&Public> for PointView {
+    fn from(public_key: &Public) -> Self {
+        PointView::from(&public_key.0)
+    }
+}
+
+/// A list of points, decomposed only when the caller asks for it; otherwise
+/// the points are left as the plain compressed-hex strings callers already
+/// get today.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum PointsView {
+    Compressed(Vec<String>),
+    Decomposed(Vec<PointView>),
+}
+
+/// Build a [`PointsView`] for `points`: compressed hex when `compress` is
+/// true (the default, and the pre-existing wire format), decomposed
+/// `x`/`y` pairs when the caller sets `compress: false`.
+pub fn points_view(points: &[AffinePoint], compress: bool) -> PointsView {
+    if compress {
+        PointsView::Compressed(
+            points
+                .iter()
+                .map(|p| {
+                    let mut buf = Vec::new();
+                    p.serialize_compressed(&mut buf).unwrap();
+                    format!("0x{}", hex::encode(buf))
+                })
+                .collect()
+        )
+    } else {
+        PointsView::Decomposed(points.iter().map(PointView::from).collect())
+    }
+}
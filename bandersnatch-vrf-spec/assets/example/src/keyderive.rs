@@ -0,0 +1,65 @@
+//! BIP32-style hierarchical secret derivation for bandersnatch provers.
+//!
+//! A prover backed by an existing wallet seed (BIP39 mnemonic or raw
+//! 32-byte master seed) can be recovered deterministically from that seed
+//! plus a derivation path, instead of relying on in-memory UUID storage.
+
+use bandersnatch::Secret;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// A derived secret plus the chain code needed to derive its children.
+pub struct ExtendedSecret {
+    pub secret: Secret,
+    pub chain_code: [u8; 32],
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> [u8; 64] {
+    let mut mac = HmacSha512::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+/// Derive the master extended secret from a raw seed.
+///
+/// `Secret::from_seed` already reduces its input into the scalar field
+/// (the same reduction used elsewhere in this crate for index-seeded
+/// secrets), so the HMAC's left 32 bytes can be handed to it directly.
+fn master_from_seed(seed: &[u8]) -> ExtendedSecret {
+    let out = hmac_sha512(b"bandersnatch seed", seed);
+    let (left, right) = out.split_at(32);
+    ExtendedSecret {
+        secret: Secret::from_seed(left),
+        chain_code: right.try_into().expect("right half of HMAC-SHA512 output is 32 bytes"),
+    }
+}
+
+/// Derive child `index` of `parent`: HMAC-SHA512 the chain code with the
+/// big-endian index, reduce the left 32 bytes into the child secret scalar,
+/// and chain the right 32 bytes as the next chain code.
+fn derive_child(parent: &ExtendedSecret, index: u32) -> ExtendedSecret {
+    let out = hmac_sha512(&parent.chain_code, &index.to_be_bytes());
+    let (left, right) = out.split_at(32);
+    ExtendedSecret {
+        secret: Secret::from_seed(left),
+        chain_code: right.try_into().expect("right half of HMAC-SHA512 output is 32 bytes"),
+    }
+}
+
+/// Derive the secret at `path` from a raw master seed.
+pub fn derive_path(master_seed: &[u8], path: &[u32]) -> ExtendedSecret {
+    let mut current = master_from_seed(master_seed);
+    for &index in path {
+        current = derive_child(&current, index);
+    }
+    current
+}
+
+/// Turn a BIP39 mnemonic phrase (plus optional passphrase) into the 64-byte
+/// seed fed to [`derive_path`].
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64], String> {
+    let mnemonic = bip39::Mnemonic::parse(mnemonic).map_err(|e| e.to_string())?;
+    Ok(mnemonic.to_seed(passphrase))
+}
@@ -0,0 +1,185 @@
+//! On-disk keystore for prover secrets, modeled on Substrate's
+//! `LocalKeystore`: each key is stored as its own file, named by a
+//! `KeyTypeId`-like 4-byte tag and the key's compressed public key, so a
+//! secret is generated or imported once and resolved by public key
+//! thereafter, rather than re-derived from a bare index on every request.
+//!
+//! Secrets are written as their 32-byte seed, optionally AES-256-GCM
+//! encrypted under a password supplied when the store is opened, with the
+//! AES key derived from that password via PBKDF2-HMAC-SHA256 under a
+//! random salt stored alongside the ciphertext. Nothing in this module ever
+//! returns a seed or secret over the API; callers resolve a public key to a
+//! [`Secret`] server-side only.
+
+use ark_vrf::reexports::ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use bandersnatch::{Public, Secret};
+use rand::RngCore;
+use sha2::Sha256;
+use std::fs;
+use std::path::PathBuf;
+
+/// Four-byte key type tag, mirroring Substrate's `KeyTypeId` (e.g.
+/// `*b"bndr"` for bandersnatch prover keys).
+pub type KeyTypeId = [u8; 4];
+
+#[derive(Debug)]
+pub enum KeyStoreError {
+    Io(std::io::Error),
+    InvalidSecret,
+    WrongPassword,
+    NotFound,
+}
+
+impl From<std::io::Error> for KeyStoreError {
+    fn from(error: std::io::Error) -> Self {
+        KeyStoreError::Io(error)
+    }
+}
+
+pub struct KeyStore {
+    dir: PathBuf,
+    password: Option<String>,
+}
+
+impl KeyStore {
+    /// Open (creating if needed) a keystore rooted at `dir`. When `password`
+    /// is set, every secret written through this handle is encrypted at
+    /// rest under it; the same password must be supplied to read it back.
+    pub fn open(dir: impl Into<PathBuf>, password: Option<String>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir, password })
+    }
+
+    fn key_path(&self, key_type: KeyTypeId, public_key: &Public) -> PathBuf {
+        let mut pk_buf = Vec::new();
+        public_key.serialize_compressed(&mut pk_buf).unwrap();
+        self.dir.join(format!("{}{}", hex::encode(key_type), hex::encode(pk_buf)))
+    }
+
+    fn persist(&self, key_type: KeyTypeId, public_key: &Public, seed: &[u8; 32]) -> Result<(), KeyStoreError> {
+        let contents = match &self.password {
+            Some(password) => encrypt(seed, password),
+            None => seed.to_vec(),
+        };
+        fs::write(self.key_path(key_type, public_key), hex::encode(contents))?;
+        Ok(())
+    }
+
+    /// Generate a fresh random secret under `key_type` and persist it,
+    /// returning its public key.
+    pub fn generate(&self, key_type: KeyTypeId) -> Result<Public, KeyStoreError> {
+        let mut seed = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut seed);
+        let public_key = Public::from(Secret::from_seed(&seed));
+        self.persist(key_type, &public_key, &seed)?;
+        Ok(public_key)
+    }
+
+    /// Import an existing 32-byte seed under `key_type`, persisting it the
+    /// same way [`Self::generate`] would. Returns its public key.
+    pub fn insert(&self, key_type: KeyTypeId, seed: &[u8; 32]) -> Result<Public, KeyStoreError> {
+        let public_key = Public::from(Secret::from_seed(seed));
+        self.persist(key_type, &public_key, seed)?;
+        Ok(public_key)
+    }
+
+    /// Resolve `public_key` back to its [`Secret`], decrypting with this
+    /// store's password if it was encrypted at rest.
+    pub fn secret(&self, key_type: KeyTypeId, public_key: &Public) -> Result<Secret, KeyStoreError> {
+        let path = self.key_path(key_type, public_key);
+        let hex_contents = fs::read_to_string(&path).map_err(|error| {
+            if error.kind() == std::io::ErrorKind::NotFound {
+                KeyStoreError::NotFound
+            } else {
+                KeyStoreError::Io(error)
+            }
+        })?;
+        let contents = hex::decode(hex_contents.trim()).map_err(|_| KeyStoreError::InvalidSecret)?;
+        let seed: [u8; 32] = match &self.password {
+            Some(password) => decrypt(&contents, password)?,
+            None => contents.try_into().map_err(|_| KeyStoreError::InvalidSecret)?,
+        };
+        Ok(Secret::from_seed(&seed))
+    }
+
+    /// All public keys currently stored under `key_type`.
+    pub fn public_keys(&self, key_type: KeyTypeId) -> std::io::Result<Vec<Public>> {
+        let prefix = hex::encode(key_type);
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            let Some(pk_hex) = name.strip_prefix(&prefix) else {
+                continue;
+            };
+            if let Ok(bytes) = hex::decode(pk_hex) {
+                if let Ok(public_key) = Public::deserialize_compressed(&bytes[..]) {
+                    keys.push(public_key);
+                }
+            }
+        }
+        Ok(keys)
+    }
+}
+
+/// PBKDF2-HMAC-SHA256 round count, roughly OWASP's 2023 floor for this
+/// construction. A bare `Sha256::digest(password)` is a single hash
+/// evaluation, so brute-forcing a weak password offline costs an attacker
+/// the same as hashing it once; this makes each guess cost this many HMAC
+/// evaluations instead.
+const AES_KEY_PBKDF2_ROUNDS: u32 = 210_000;
+
+/// Requires the `pbkdf2` and `hmac` crates. Add them to `Cargo.toml` if
+/// they aren't already present.
+///
+/// `salt` must be unique per encrypted file (see [`encrypt`]/[`decrypt`]):
+/// without it, the same password would derive the same AES key everywhere,
+/// so a precomputed table of common passwords' keys could be reused across
+/// every keystore on disk instead of needing to be rebuilt per file.
+fn aes_key(password: &str, salt: &[u8; 16]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2::<hmac::Hmac<Sha256>>(password.as_bytes(), salt, AES_KEY_PBKDF2_ROUNDS, &mut key)
+        .expect("PBKDF2-HMAC-SHA256's output length is fixed at 32 bytes, matching `key`");
+    key
+}
+
+/// Requires the `aes-gcm` crate. Add it to `Cargo.toml` if it isn't
+/// already present.
+fn encrypt(seed: &[u8; 32], password: &str) -> Vec<u8> {
+    use aes_gcm::aead::{generic_array::GenericArray, Aead};
+    use aes_gcm::{Aes256Gcm, KeyInit};
+
+    let mut salt = [0u8; 16];
+    rand::rngs::OsRng.fill_bytes(&mut salt);
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&aes_key(password, &salt)));
+    let mut nonce_bytes = [0u8; 12];
+    rand::rngs::OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = GenericArray::from_slice(&nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, seed.as_slice())
+        .expect("encrypting a 32-byte seed under a freshly generated nonce cannot fail");
+
+    let mut out = Vec::with_capacity(salt.len() + nonce_bytes.len() + ciphertext.len());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+fn decrypt(contents: &[u8], password: &str) -> Result<[u8; 32], KeyStoreError> {
+    use aes_gcm::aead::{generic_array::GenericArray, Aead};
+    use aes_gcm::{Aes256Gcm, KeyInit};
+
+    if contents.len() < 16 + 12 {
+        return Err(KeyStoreError::WrongPassword);
+    }
+    let (salt, rest) = contents.split_at(16);
+    let (nonce_bytes, ciphertext) = rest.split_at(12);
+    let salt: [u8; 16] = salt.try_into().expect("split_at(16) guarantees a 16-byte slice");
+    let cipher = Aes256Gcm::new(GenericArray::from_slice(&aes_key(password, &salt)));
+    let plaintext = cipher
+        .decrypt(GenericArray::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| KeyStoreError::WrongPassword)?;
+    plaintext.try_into().map_err(|_| KeyStoreError::InvalidSecret)
+}
@@ -4,7 +4,7 @@ use wasm_bindgen::prelude::*;
 use ark_vrf::reexports::{
     ark_serialize::{CanonicalDeserialize, CanonicalSerialize},
 };
-use bandersnatch::{Public, Secret, RingProofParams, Input, Output, RingProof};
+use bandersnatch::{BandersnatchSha512Ell2, Public, Secret, RingProofParams, Input, Output, RingProof};
 use ark_vrf::suites::bandersnatch;
 use std::sync::OnceLock;
 use ark_vrf::ring::{Prover, Verifier};
@@ -29,66 +29,180 @@ fn ring_proof_params() -> &'static RingProofParams {
     static PARAMS: OnceLock<RingProofParams> = OnceLock::new();
     PARAMS.get_or_init(|| {
         use bandersnatch::PcsParams;
-        let pcs_params = PcsParams::deserialize_uncompressed_unchecked(&mut &SRS_BYTES[..]).unwrap();
-        RingProofParams::from_pcs_params(1023, pcs_params).unwrap()
+        let pcs_params = PcsParams::deserialize_uncompressed_unchecked(&mut &SRS_BYTES[..])
+            .expect("bundled SRS bytes are well-formed");
+        RingProofParams::from_pcs_params(1023, pcs_params)
+            .expect("bundled SRS matches the configured ring size")
     })
 }
 
-fn vrf_input_point(vrf_input_data: &[u8]) -> Input {
-    Input::new(vrf_input_data).unwrap()
+fn js_err(message: impl Into<String>) -> JsValue {
+    JsValue::from_str(&message.into())
+}
+
+fn vrf_input_point(vrf_input_data: &[u8]) -> Result<Input, JsValue> {
+    Input::new(vrf_input_data).ok_or_else(|| js_err("invalid VRF input data"))
+}
+
+/// Deserialize `ring` (concatenated 32-byte compressed public keys) into
+/// `Public`s, failing with a `JsValue` error instead of panicking the WASM
+/// instance on the first malformed chunk.
+fn deserialize_ring(ring: &[u8]) -> Result<Vec<Public>, JsValue> {
+    ring.chunks(32)
+        .map(|chunk| {
+            Public::deserialize_compressed(chunk).map_err(|_| js_err("invalid public key in ring"))
+        })
+        .collect()
+}
+
+/// The position of `public` within `ring_keys`, found by compressed-bytes
+/// equality since `Public` has no `PartialEq`. `Err` if the signer's key
+/// isn't a member of the ring at all.
+fn ring_position(ring_keys: &[Public], public: &Public) -> Result<usize, JsValue> {
+    let mut target = Vec::new();
+    public
+        .serialize_compressed(&mut target)
+        .map_err(|_| js_err("failed to serialize signer's public key"))?;
+    ring_keys
+        .iter()
+        .position(|candidate| {
+            let mut candidate_bytes = Vec::new();
+            candidate.serialize_compressed(&mut candidate_bytes).is_ok() && candidate_bytes == target
+        })
+        .ok_or_else(|| js_err("signer's public key is not a member of the supplied ring"))
 }
 
 #[wasm_bindgen]
-pub fn ringvrf_prove(message: &[u8], ring: &[u8], priv_key: &[u8]) -> Vec<u8> {
-    // ring: concatenated public keys (32 bytes each)
-    let ring_keys: Vec<Public> = ring.chunks(32).map(|b| Public::deserialize_compressed(b).unwrap()).collect();
-    let secret = Secret::deserialize_compressed(priv_key).unwrap();
-    let input = vrf_input_point(message);
+pub fn ringvrf_prove(message: &[u8], ring: &[u8], priv_key: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let ring_keys = deserialize_ring(ring)?;
+    let secret = Secret::deserialize_compressed(priv_key).map_err(|_| js_err("invalid secret key"))?;
+    let public = Public::from(secret.clone());
+    // The prover index is the signer's position in the ring; deriving it by
+    // matching against the ring (rather than hardcoding 0) is required for
+    // a correct ring proof whenever the signer isn't the ring's first member.
+    let prover_idx = ring_position(&ring_keys, &public)?;
+
+    let input = vrf_input_point(message)?;
     let output = secret.output(input);
     let pts: Vec<_> = ring_keys.iter().map(|pk| pk.0).collect();
     let params = ring_proof_params();
     let prover_key = params.prover_key(&pts);
-    let prover = params.prover(prover_key, 0); // index unknown, not needed for proof
+    let prover = params.prover(prover_key, prover_idx);
     let proof = secret.prove(input, output, b"", &prover);
-    // Output and Ring Proof bundled together
-    let signature = crate::RingVrfSignature { output, proof };
+
+    let signature = RingVrfSignature { output, proof };
     let mut buf = Vec::new();
-    signature.serialize_compressed(&mut buf).unwrap();
-    buf
+    signature
+        .serialize_compressed(&mut buf)
+        .map_err(|_| js_err("failed to serialize ring VRF signature"))?;
+    Ok(buf)
 }
 
 #[wasm_bindgen]
-pub fn ringvrf_verify(message: &[u8], ring: &[u8], proof: &[u8]) -> JsValue {
-    let ring_keys: Vec<Public> = ring.chunks(32).map(|b| Public::deserialize_compressed(b).unwrap()).collect();
-    let input = vrf_input_point(message);
+pub fn ringvrf_verify(message: &[u8], ring: &[u8], proof: &[u8]) -> Result<JsValue, JsValue> {
+    let ring_keys = deserialize_ring(ring)?;
+    let input = vrf_input_point(message)?;
     let params = ring_proof_params();
     let pts: Vec<_> = ring_keys.iter().map(|pk| pk.0).collect();
     let verifier_key = params.verifier_key(&pts);
-    let commitment = verifier_key.commitment();
     let verifier = params.verifier(verifier_key);
-    let signature = crate::RingVrfSignature::deserialize_compressed(proof).unwrap();
+    let signature =
+        RingVrfSignature::deserialize_compressed(proof).map_err(|_| js_err("invalid ring VRF signature"))?;
     let output = signature.output;
     let ok = bandersnatch::Public::verify(input, output, b"", &signature.proof, &verifier).is_ok();
-    let output_bytes = output.hash()[..32].to_vec();
-    // Return JS object: { ok: bool, output: Uint8Array }
-    let result = js_sys::Object::new();
-    js_sys::Reflect::set(&result, &"ok".into(), &JsValue::from_bool(ok)).unwrap();
-    js_sys::Reflect::set(&result, &"output".into(), &js_sys::Uint8Array::from(&output_bytes[..])).unwrap();
-    result.into()
+    build_verify_result(ok, &output)
 }
 
 #[wasm_bindgen]
-pub fn compose_ring_root(ring: &[u8]) -> Vec<u8> {
-    let ring_keys: Vec<Public> = ring.chunks(32)
-        .map(|b| Public::deserialize_compressed(b).unwrap())
-        .collect();
+pub fn compose_ring_root(ring: &[u8]) -> Result<Vec<u8>, JsValue> {
+    let ring_keys = deserialize_ring(ring)?;
     let pts: Vec<_> = ring_keys.iter().map(|pk| pk.0).collect();
     let params = ring_proof_params();
     let verifier_key = params.verifier_key(&pts);
     let commitment = verifier_key.commitment();
     let mut buf = Vec::new();
-    commitment.serialize_compressed(&mut buf).unwrap();
-    buf
+    commitment
+        .serialize_compressed(&mut buf)
+        .map_err(|_| js_err("failed to serialize ring commitment"))?;
+    Ok(buf)
+}
+
+type RingCommitment = ark_vrf::ring::RingCommitment<BandersnatchSha512Ell2>;
+
+fn build_verify_result(ok: bool, output: &Output) -> Result<JsValue, JsValue> {
+    let output_bytes = output.hash()[..32].to_vec();
+    let result = js_sys::Object::new();
+    js_sys::Reflect::set(&result, &"ok".into(), &JsValue::from_bool(ok))
+        .map_err(|_| js_err("failed to build result object"))?;
+    js_sys::Reflect::set(&result, &"output".into(), &js_sys::Uint8Array::from(&output_bytes[..]))
+        .map_err(|_| js_err("failed to build result object"))?;
+    Ok(result.into())
+}
+
+/// A ring root (verifier commitment), derived once and reused across many
+/// verifications against the same ring. Building this caches the
+/// commitment so repeated verification calls skip re-parsing `ring` and
+/// re-deriving the verifier key's commitment from the ring's points each
+/// time — only `WasmRingContext::new` pays that cost.
+#[wasm_bindgen]
+pub struct WasmRingContext {
+    commitment: RingCommitment,
+}
+
+#[wasm_bindgen]
+impl WasmRingContext {
+    #[wasm_bindgen(constructor)]
+    pub fn new(ring: &[u8]) -> Result<WasmRingContext, JsValue> {
+        let ring_keys = deserialize_ring(ring)?;
+        let pts: Vec<_> = ring_keys.iter().map(|pk| pk.0).collect();
+        let verifier_key = ring_proof_params().verifier_key(&pts);
+        let commitment = verifier_key.commitment();
+        Ok(WasmRingContext { commitment })
+    }
+
+    /// Verify a single ring VRF proof against this context's cached ring
+    /// root, without rebuilding the verifier key from the ring's points.
+    pub fn verify(&self, message: &[u8], proof: &[u8]) -> Result<JsValue, JsValue> {
+        let input = vrf_input_point(message)?;
+        let params = ring_proof_params();
+        let verifier_key = params.verifier_key_from_commitment(self.commitment.clone());
+        let verifier = params.verifier(verifier_key);
+        let signature = RingVrfSignature::deserialize_compressed(proof)
+            .map_err(|_| js_err("invalid ring VRF signature"))?;
+        let output = signature.output;
+        let ok = bandersnatch::Public::verify(input, output, b"", &signature.proof, &verifier).is_ok();
+        build_verify_result(ok, &output)
+    }
+}
+
+/// Verify a batch of ring VRF proofs against one ring root, deriving the
+/// verifier key's commitment once (via [`WasmRingContext`]) instead of once
+/// per proof. `proofs` is a simple length-prefixed concatenation: each
+/// entry is a little-endian `u32` byte length followed by that many
+/// compressed-signature bytes. Returns a JS array of `{ ok, output }`
+/// results, one per entry, in order.
+#[wasm_bindgen]
+pub fn ringvrf_verify_batch(message: &[u8], ring: &[u8], proofs: &[u8]) -> Result<JsValue, JsValue> {
+    let ctx = WasmRingContext::new(ring)?;
+
+    let array = js_sys::Array::new();
+    let mut offset = 0usize;
+    while offset < proofs.len() {
+        if offset + 4 > proofs.len() {
+            return Err(js_err("truncated proof length prefix"));
+        }
+        let len = u32::from_le_bytes(proofs[offset..offset + 4].try_into().unwrap()) as usize;
+        offset += 4;
+        if offset + len > proofs.len() {
+            return Err(js_err("truncated proof body"));
+        }
+        let proof = &proofs[offset..offset + len];
+        offset += len;
+
+        array.push(&ctx.verify(message, proof)?);
+    }
+
+    Ok(array.into())
 }
 
 // Helper struct for serialization
@@ -96,4 +210,4 @@ pub fn compose_ring_root(ring: &[u8]) -> Vec<u8> {
 pub struct RingVrfSignature {
     output: Output,
     proof: RingProof,
-} 
\ No newline at end of file
+}
@@ -8,6 +8,7 @@ use bandersnatch::{
     RingProofParams, Secret,
 };
 use axum::{extract::Json, routing::{get, post}, Router, http::StatusCode};
+use parity_scale_codec::{Compact, Decode, Encode};
 use serde::{Deserialize, Serialize};
 use tokio::net::TcpListener;
 use std::collections::HashMap as StdHashMap;
@@ -15,6 +16,16 @@ use std::sync::{Arc, Mutex};
 use uuid::Uuid; // Ensure Uuid is in scope
 const RING_SIZE: usize = 6;
 
+mod conformance;
+// Requires the `hmac`, `sha2`, and `bip39` crates. Add them to `Cargo.toml`
+// if they aren't already present.
+mod keyderive;
+mod curve_view;
+// Requires the `aes-gcm` crate. Add it to `Cargo.toml` if it isn't already
+// present.
+mod keystore;
+mod threshold;
+
 // This is the IETF `Prove` procedure output as described in section 2.2
 // of the Bandersnatch VRF specification
 #[derive(CanonicalSerialize, CanonicalDeserialize)]
@@ -32,21 +43,62 @@ struct RingVrfSignature {
     proof: RingProof,
 }
 
-// "Static" ring proof parameters.
-fn ring_proof_params() -> &'static RingProofParams {
+/// The SRS (`zcash-srs-2-11-uncompressed.bin`) supports ring commitments up
+/// to a domain of 2^11; this is the largest ring any size-specific
+/// `RingProofParams` below can be derived for.
+const MAX_RING_SIZE: usize = 1 << 11;
+
+// The raw PCS params, loaded from disk exactly once regardless of how many
+// distinct ring sizes are later requested.
+fn loaded_pcs_params() -> &'static bandersnatch::PcsParams {
+    use bandersnatch::PcsParams;
     use std::sync::OnceLock;
-    static PARAMS: OnceLock<RingProofParams> = OnceLock::new();
-    PARAMS.get_or_init(|| {
-        use bandersnatch::PcsParams;
-        use std::{fs::File, io::Read};
+    use std::{fs::File, io::Read};
+    static PCS_PARAMS: OnceLock<PcsParams> = OnceLock::new();
+    PCS_PARAMS.get_or_init(|| {
         let manifest_dir =
             std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR is not set");
         let filename = format!("{}/data/zcash-srs-2-11-uncompressed.bin", manifest_dir);
         let mut file = File::open(filename).unwrap();
         let mut buf = Vec::new();
         file.read_to_end(&mut buf).unwrap();
-        let pcs_params = PcsParams::deserialize_uncompressed_unchecked(&mut &buf[..]).unwrap();
-        RingProofParams::from_pcs_params(RING_SIZE, pcs_params).unwrap()
+        PcsParams::deserialize_uncompressed_unchecked(&mut &buf[..]).unwrap()
+    })
+}
+
+/// Round `n` up to the next ring size this deployment has (or will lazily
+/// derive) `RingProofParams` for. JAM rings are expected to land on
+/// validator-set-shaped sizes, so power-of-two steps keep the param cache
+/// small without limiting the server to a single fixed ring.
+fn next_supported_ring_size(n: usize) -> usize {
+    let mut size = 1usize;
+    while size < n {
+        size *= 2;
+    }
+    size.max(2).min(MAX_RING_SIZE)
+}
+
+/// Pad `ring` with `BandersnatchSha512Ell2::PADDING` up to the next
+/// supported ring size, so a caller doesn't need to know the bound itself.
+fn pad_ring(mut ring: Vec<Public>) -> Vec<Public> {
+    let target = next_supported_ring_size(ring.len());
+    let padding = Public::from(BandersnatchSha512Ell2::PADDING);
+    ring.resize(target, padding);
+    ring
+}
+
+/// Size-parameterized `RingProofParams` cache. Each entry is derived once
+/// from the single loaded `PcsParams` and then leaked to hand out a
+/// `'static` reference, mirroring the old single-size `OnceLock` but keyed
+/// on ring size instead of hard-coding `RING_SIZE`.
+fn ring_proof_params(ring_size: usize) -> &'static RingProofParams {
+    use std::sync::{Mutex, OnceLock};
+    static CACHE: OnceLock<Mutex<StdHashMap<usize, &'static RingProofParams>>> = OnceLock::new();
+    let cache = CACHE.get_or_init(|| Mutex::new(StdHashMap::new()));
+    let mut cache = cache.lock().unwrap();
+    *cache.entry(ring_size).or_insert_with(|| {
+        let params = RingProofParams::from_pcs_params(ring_size, loaded_pcs_params().clone()).unwrap();
+        Box::leak(Box::new(params))
     })
 }
 
@@ -55,6 +107,46 @@ fn vrf_input_point(vrf_input_data: &[u8]) -> Input {
     Input::new(vrf_input_data).unwrap()
 }
 
+/// HKDF-style expansion used by [`Prover::vrf_bytes`]: hash `context || src
+/// || counter` with SHA-512 for successive counters until `len` bytes have
+/// been produced, then truncate to exactly `len`. Mirrors the transcript
+/// absorb-then-squeeze shape BABE/Sassafras use to turn a VRF pre-output
+/// into authoring randomness, without pulling in a full transcript crate.
+fn expand_transcript(context: &[u8], src: &[u8], len: usize) -> Vec<u8> {
+    use sha2::{Digest, Sha512};
+
+    let mut out = Vec::with_capacity(len + 64);
+    let mut counter: u32 = 0;
+    while out.len() < len {
+        let mut hasher = Sha512::new();
+        hasher.update(context);
+        hasher.update(src);
+        hasher.update(counter.to_le_bytes());
+        out.extend_from_slice(&hasher.finalize());
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+/// Canonical `jam_ticket_seal` VRF input: the domain separator, the epoch's
+/// entropy `eta`, and the raw big-endian attempt byte — the same layout
+/// `ring_vrf_verify_payload_handler`, `verify_one_ticket`, and the
+/// SCALE-envelope verify path each build by hand. `/prover/make_ticket` and
+/// `/verifier/rank_tickets` go through this function; the
+/// `ticket_made_via_ticket_vrf_input_verifies_via_verify_one_ticket` test
+/// checks that its layout matches `verify_one_ticket`'s.
+/// `attempt_index` is widened to `u32` in the request/response JSON, but the
+/// wire-level attempt is always a single byte, matching `ExtrinsicItem`'s
+/// `attempt: u8` elsewhere.
+fn ticket_vrf_input(eta: &[u8], attempt_index: u32) -> Vec<u8> {
+    let mut input = Vec::new();
+    input.extend_from_slice(b"jam_ticket_seal");
+    input.extend_from_slice(eta);
+    input.push(attempt_index as u8);
+    input
+}
+
 // Prover actor.
 struct Prover {
     pub prover_idx: usize,
@@ -67,7 +159,7 @@ impl Prover {
         Self {
             prover_idx,
             secret: Secret::from_seed(&prover_idx.to_le_bytes()),
-            ring,
+            ring: pad_ring(ring),
         }
     }
 
@@ -78,6 +170,19 @@ impl Prover {
         output.hash()[..32].try_into().unwrap()
     }
 
+    /// Derive `len` bytes of pseudo-random output from this prover's secret
+    /// at `vrf_input_data`, domain-separated by `context`. The VRF
+    /// pre-output point is absorbed alongside the context label and then
+    /// expanded to exactly `len` bytes, the same shape consensus layers use
+    /// to turn a ticket's VRF output into slot/authoring randomness.
+    pub fn vrf_bytes(&self, vrf_input_data: &[u8], context: &[u8], len: usize) -> Vec<u8> {
+        let input = vrf_input_point(vrf_input_data);
+        let output = self.secret.output(input);
+        let mut output_buf = Vec::new();
+        output.serialize_compressed(&mut output_buf).unwrap();
+        expand_transcript(context, &output_buf, len)
+    }
+
     /// Anonymous VRF signature.
     ///
     /// Used for tickets submission.
@@ -91,7 +196,7 @@ impl Prover {
         let pts: Vec<_> = self.ring.iter().map(|pk| pk.0).collect();
 
         // Proof construction
-        let params = ring_proof_params();
+        let params = ring_proof_params(self.ring.len());
         let prover_key = params.prover_key(&pts);
         let prover = params.prover(prover_key, self.prover_idx);
         let proof = self.secret.prove(input, output, aux_data, &prover);
@@ -133,9 +238,10 @@ struct Verifier {
 
 impl Verifier {
     fn new(ring: Vec<Public>) -> Self {
+        let ring = pad_ring(ring);
         // Backend currently requires the wrapped type (plain affine points)
         let pts: Vec<_> = ring.iter().map(|pk| pk.0).collect();
-        let verifier_key = ring_proof_params().verifier_key(&pts);
+        let verifier_key = ring_proof_params(ring.len()).verifier_key(&pts);
         let commitment = verifier_key.commitment();
         Self { ring, commitment }
     }
@@ -158,7 +264,7 @@ impl Verifier {
         let input = vrf_input_point(vrf_input_data);
         let output = signature.output;
 
-        let params = ring_proof_params();
+        let params = ring_proof_params(self.ring.len());
 
         let verifier_key = params.verifier_key_from_commitment(self.commitment.clone());
         let verifier = params.verifier(verifier_key);
@@ -230,7 +336,10 @@ fn print_points() {
 
 // In main.rs
 
-fn compose_gamma_z(public_keys: &[String]) -> Vec<u8> {
+/// Parse `public_keys` into ring members, substituting the zero key for the
+/// conventional all-zero padding placeholder, then pad up to the next
+/// supported ring size.
+fn parse_and_pad_ring(public_keys: &[String]) -> Vec<Public> {
     const PADDING_KEY_HEX: &str = "0000000000000000000000000000000000000000000000000000000000000000";
     let mut ring_keys = Vec::new();
 
@@ -248,9 +357,16 @@ fn compose_gamma_z(public_keys: &[String]) -> Vec<u8> {
         }
     }
 
-    // The rest of the function remains the same
+    // Auto-pad up to the next supported size; a ring whose length (explicit
+    // padding keys included) is already a supported size is left unchanged.
+    pad_ring(ring_keys)
+}
+
+fn compose_gamma_z(public_keys: &[String]) -> Vec<u8> {
+    let ring_keys = parse_and_pad_ring(public_keys);
+
     let pts: Vec<_> = ring_keys.iter().map(|pk| pk.0).collect();
-    let params = ring_proof_params();
+    let params = ring_proof_params(ring_keys.len());
     let verifier_key = params.verifier_key(&pts);
     let commitment = verifier_key.commitment();
     let mut buf = Vec::new();
@@ -261,17 +377,37 @@ fn compose_gamma_z(public_keys: &[String]) -> Vec<u8> {
 #[derive(Deserialize)]
 struct GammaZRequest {
     public_keys: Vec<String>,
+    /// When `false`, `ring` in the response decomposes each padded ring
+    /// member into its affine `x`/`y` coordinates instead of compressed
+    /// hex. Defaults to `true` (the pre-existing, compact behavior).
+    #[serde(default = "default_compress")]
+    compress: bool,
+    /// When true, `gamma_z` is the hex-wrapped SCALE `Encode` of the
+    /// commitment bytes (a compact length prefix followed by the raw
+    /// bytes) instead of plain compressed hex.
+    #[serde(default)]
+    scale: bool,
+}
+
+fn default_compress() -> bool {
+    true
 }
 
 #[derive(Serialize)]
 struct GammaZResponse {
     gamma_z: String,
+    ring: curve_view::PointsView,
 }
 
 #[derive(Deserialize)]
 struct CreateProverRequest {
     public_keys: Vec<String>,
     prover_index: usize,
+    /// Keystore tag (e.g. `"bndr"`) the secret for `public_keys[prover_index]`
+    /// was generated or imported under. The secret is resolved from the
+    /// keystore by this key type plus the public key itself, never
+    /// re-derived from `prover_index`.
+    key_type: String,
 }
 
 #[derive(Serialize)]
@@ -280,6 +416,56 @@ struct CreateProverResponse {
     public_key: String,
 }
 
+#[derive(Deserialize)]
+struct KeystoreGenerateRequest {
+    key_type: String,
+}
+
+#[derive(Serialize)]
+struct KeystoreGenerateResponse {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct KeystoreInsertRequest {
+    key_type: String,
+    /// 32-byte secret seed, hex-encoded.
+    seed_hex: String,
+}
+
+#[derive(Serialize)]
+struct KeystoreInsertResponse {
+    public_key: String,
+}
+
+#[derive(Deserialize)]
+struct KeystorePublicKeysRequest {
+    key_type: String,
+}
+
+#[derive(Serialize)]
+struct KeystorePublicKeysResponse {
+    public_keys: Vec<String>,
+}
+
+/// Import a prover whose secret is derived from a wallet seed rather than
+/// resolved from the keystore. Exactly one of `mnemonic`/`seed_hex` must be set.
+#[derive(Deserialize)]
+struct ImportProverRequest {
+    mnemonic: Option<String>,
+    seed_hex: Option<String>,
+    passphrase: Option<String>,
+    derivation_path: Vec<u32>,
+    public_keys: Vec<String>,
+    prover_index: usize,
+}
+
+#[derive(Serialize)]
+struct ImportProverResponse {
+    prover_id: String,
+    public_key: String,
+}
+
 #[derive(Deserialize)]
 struct VrfOutputRequest {
     prover_id: String,
@@ -291,11 +477,34 @@ struct VrfOutputResponse {
     vrf_output_hash: String,
 }
 
+#[derive(Deserialize)]
+struct VrfBytesRequest {
+    prover_id: String,
+    vrf_input_data: String,
+    /// Domain-separation label, e.g. `"SassafrasTicketBody"`.
+    context: String,
+    len: usize,
+    /// Decimal `u128`; when set, the response's `below_threshold` compares
+    /// the first 16 output bytes (little-endian) against it.
+    threshold: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VrfBytesResponse {
+    bytes: String,
+    below_threshold: Option<bool>,
+}
+
 #[derive(Deserialize)]
 struct RingVrfSignRequest {
     prover_id: String,
     vrf_input_data: String,
     aux_data: String,
+    /// When true, `signature` is the SCALE-encoded `ScaleRingVrfSignature`
+    /// (the same field layout `/scale/ring_vrf/sign` returns, hex-wrapped)
+    /// instead of this crate's default ark-compressed hex.
+    #[serde(default)]
+    scale: bool,
 }
 
 #[derive(Serialize)]
@@ -308,6 +517,9 @@ struct IetfVrfSignRequest {
     prover_id: String,
     vrf_input_data: String,
     aux_data: String,
+    /// See [`RingVrfSignRequest::scale`].
+    #[serde(default)]
+    scale: bool,
 }
 
 #[derive(Serialize)]
@@ -315,6 +527,100 @@ struct IetfVrfSignResponse {
     signature: String,
 }
 
+#[derive(Deserialize)]
+struct MakeTicketRequest {
+    prover_id: String,
+    eta: String,
+    attempt_index: u32,
+}
+
+#[derive(Serialize)]
+struct MakeTicketResponse {
+    ticket_id: String,
+    attempt: u32,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct RankTicketsItem {
+    attempt: u32,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct RankTicketsRequest {
+    verifier_id: String,
+    eta: String,
+    tickets: Vec<RankTicketsItem>,
+}
+
+#[derive(Serialize)]
+struct RankedTicket {
+    ticket_id: String,
+    attempt: u32,
+    signature: String,
+}
+
+#[derive(Serialize)]
+struct RankTicketsResponse {
+    tickets: Vec<RankedTicket>,
+}
+
+#[derive(Deserialize)]
+struct ThresholdKeygenRequest {
+    public_keys: Vec<String>,
+    prover_index: usize,
+    threshold: usize,
+    participants: usize,
+}
+
+#[derive(Serialize)]
+struct ThresholdKeygenResponse {
+    session_id: String,
+    group_public_key: String,
+    /// Share `i` (1-indexed) for participant `i`; the dealer does not
+    /// retain a copy of these after returning them.
+    shares: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct ThresholdCommitRequest {
+    session_id: String,
+    participant_index: u32,
+    commitment: String,
+}
+
+#[derive(Serialize)]
+struct ThresholdCommitResponse {
+    accepted: bool,
+}
+
+#[derive(Deserialize)]
+struct ThresholdSignPartialRequest {
+    session_id: String,
+    participant_index: u32,
+    share: String,
+}
+
+#[derive(Serialize)]
+struct ThresholdSignPartialResponse {
+    accepted: bool,
+    partials_received: usize,
+    participants_required: usize,
+}
+
+#[derive(Deserialize)]
+struct ThresholdAggregateRequest {
+    session_id: String,
+    vrf_input_data: String,
+    aux_data: String,
+}
+
+#[derive(Serialize)]
+struct ThresholdAggregateResponse {
+    signature: String,
+}
+
 #[derive(Deserialize)]
 struct CreateVerifierRequest {
     public_keys: Vec<String>,
@@ -332,6 +638,10 @@ struct RingVrfVerifyRequest {
     vrf_input_data: String,
     aux_data: String,
     signature: String,
+    /// When true, `signature` is hex-wrapped SCALE-encoded
+    /// `ScaleRingVrfSignature` rather than ark-compressed bytes.
+    #[serde(default)]
+    scale: bool,
 }
 
 #[derive(Deserialize)]
@@ -354,6 +664,31 @@ struct RingVrfVerifyResponse {
     vrf_output_hash: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct RingVrfVerifyBatchItem {
+    vrf_input_data: String,
+    aux_data: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct RingVrfVerifyBatchRequest {
+    verifier_id: String,
+    items: Vec<RingVrfVerifyBatchItem>,
+}
+
+#[derive(Serialize)]
+struct RingVrfVerifyBatchItemResult {
+    verified: bool,
+    vrf_output_hash: Option<String>,
+}
+
+#[derive(Serialize)]
+struct RingVrfVerifyBatchResponse {
+    all_verified: bool,
+    results: Vec<RingVrfVerifyBatchItemResult>,
+}
+
 #[derive(Serialize)]
 struct RingVrfVerifyPayloadResponse {
     results: Vec<TicketVerificationResult>,
@@ -374,6 +709,10 @@ struct IetfVrfVerifyRequest {
     aux_data: String,
     signature: String,
     signer_key_index: usize,
+    /// When true, `signature` is hex-wrapped SCALE-encoded
+    /// `ScaleIetfVrfSignature` rather than ark-compressed bytes.
+    #[serde(default)]
+    scale: bool,
 }
 
 #[derive(Serialize)]
@@ -410,17 +749,29 @@ struct ApiDocResponse {
 // Global storage for provers and verifiers
 type ProverStorage = Arc<Mutex<StdHashMap<String, Prover>>>;
 type VerifierStorage = Arc<Mutex<StdHashMap<String, Verifier>>>;
+type KeyStoreHandle = Arc<keystore::KeyStore>;
+type ThresholdStorage = Arc<Mutex<StdHashMap<String, threshold::ThresholdSession>>>;
+type AppState = (ProverStorage, VerifierStorage, KeyStoreHandle, ThresholdStorage);
+
+/// Parse a `key_type` string (e.g. `"bndr"`) into the 4-byte tag the
+/// keystore addresses keys by.
+fn key_type_tag(key_type: &str) -> Result<keystore::KeyTypeId, StatusCode> {
+    key_type.as_bytes().try_into().map_err(|_| StatusCode::BAD_REQUEST)
+}
 
 // API Handlers
 async fn compose_gamma_z_handler(Json(req): Json<GammaZRequest>) -> Json<GammaZResponse> {
     let gamma_z_bytes = compose_gamma_z(&req.public_keys);
+    let gamma_z_bytes = if req.scale { gamma_z_bytes.encode() } else { gamma_z_bytes };
+    let ring_points: Vec<AffinePoint> = parse_and_pad_ring(&req.public_keys).iter().map(|pk| pk.0).collect();
     Json(GammaZResponse {
         gamma_z: format!("0x{}", hex::encode(gamma_z_bytes)),
+        ring: curve_view::points_view(&ring_points, req.compress),
     })
 }
 
 async fn create_prover_handler(
-    axum::extract::State((prover_storage, _)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((prover_storage, _, keystore, _)): axum::extract::State<AppState>,
     Json(req): Json<CreateProverRequest>,
 ) -> Result<Json<CreateProverResponse>, StatusCode> {
     let mut ring_keys = Vec::new();
@@ -436,11 +787,18 @@ async fn create_prover_handler(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let prover = Prover::new(ring_keys.clone(), req.prover_index);
+    let key_type = key_type_tag(&req.key_type)?;
+    let public_key = ring_keys[req.prover_index].clone();
+    let secret = keystore.secret(key_type, &public_key).map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let prover = Prover {
+        prover_idx: req.prover_index,
+        secret,
+        ring: pad_ring(ring_keys),
+    };
     // This line requires the `v4` feature in `Cargo.toml` for the `uuid` crate.
     let prover_id = Uuid::new_v4().to_string();
 
-    let public_key = &ring_keys[req.prover_index];
     let mut pk_buf = Vec::new();
     public_key.serialize_compressed(&mut pk_buf).unwrap();
 
@@ -452,8 +810,113 @@ async fn create_prover_handler(
     }))
 }
 
+async fn keystore_generate_handler(
+    axum::extract::State((_, _, keystore, _)): axum::extract::State<AppState>,
+    Json(req): Json<KeystoreGenerateRequest>,
+) -> Result<Json<KeystoreGenerateResponse>, StatusCode> {
+    let key_type = key_type_tag(&req.key_type)?;
+    let public_key = keystore.generate(key_type).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut pk_buf = Vec::new();
+    public_key.serialize_compressed(&mut pk_buf).unwrap();
+    Ok(Json(KeystoreGenerateResponse { public_key: format!("0x{}", hex::encode(pk_buf)) }))
+}
+
+async fn keystore_insert_handler(
+    axum::extract::State((_, _, keystore, _)): axum::extract::State<AppState>,
+    Json(req): Json<KeystoreInsertRequest>,
+) -> Result<Json<KeystoreInsertResponse>, StatusCode> {
+    let key_type = key_type_tag(&req.key_type)?;
+    let seed_bytes = hex::decode(req.seed_hex.trim_start_matches("0x"))
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let seed: [u8; 32] = seed_bytes.try_into().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let public_key = keystore.insert(key_type, &seed).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut pk_buf = Vec::new();
+    public_key.serialize_compressed(&mut pk_buf).unwrap();
+    Ok(Json(KeystoreInsertResponse { public_key: format!("0x{}", hex::encode(pk_buf)) }))
+}
+
+async fn keystore_public_keys_handler(
+    axum::extract::State((_, _, keystore, _)): axum::extract::State<AppState>,
+    Json(req): Json<KeystorePublicKeysRequest>,
+) -> Result<Json<KeystorePublicKeysResponse>, StatusCode> {
+    let key_type = key_type_tag(&req.key_type)?;
+    let public_keys = keystore.public_keys(key_type).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let public_keys = public_keys
+        .into_iter()
+        .map(|pk| {
+            let mut buf = Vec::new();
+            pk.serialize_compressed(&mut buf).unwrap();
+            format!("0x{}", hex::encode(buf))
+        })
+        .collect();
+    Ok(Json(KeystorePublicKeysResponse { public_keys }))
+}
+
+/// Like `/prover/create`, but derives the prover's secret deterministically
+/// from a BIP39 mnemonic (or a raw master seed) plus a BIP32-style
+/// derivation path, instead of resolving it from the keystore. Re-importing
+/// the same mnemonic/seed and path always recovers the same secret and
+/// public key.
+async fn import_prover_handler(
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
+    Json(req): Json<ImportProverRequest>,
+) -> Result<Json<ImportProverResponse>, StatusCode> {
+    let master_seed: Vec<u8> = match (&req.mnemonic, &req.seed_hex) {
+        (Some(_), Some(_)) => {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        (Some(mnemonic), None) =>
+            keyderive
+                ::mnemonic_to_seed(mnemonic, req.passphrase.as_deref().unwrap_or(""))
+                .map_err(|_| StatusCode::BAD_REQUEST)?
+                .to_vec(),
+        (None, Some(seed_hex)) =>
+            hex::decode(seed_hex.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?,
+        (None, None) => {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    };
+
+    let derived = keyderive::derive_path(&master_seed, &req.derivation_path);
+
+    let mut ring_keys = Vec::new();
+    for pk_hex in &req.public_keys {
+        let bytes = hex::decode(pk_hex.trim_start_matches("0x"))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let pk = Public::deserialize_compressed(&bytes[..])
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        ring_keys.push(pk);
+    }
+
+    if req.prover_index >= ring_keys.len() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let public_key = Public::from(derived.secret.clone());
+    let mut pk_buf = Vec::new();
+    public_key.serialize_compressed(&mut pk_buf).unwrap();
+
+    let prover = Prover {
+        prover_idx: req.prover_index,
+        secret: derived.secret,
+        ring: pad_ring(ring_keys),
+    };
+    // This line requires the `v4` feature in `Cargo.toml` for the `uuid` crate.
+    let prover_id = Uuid::new_v4().to_string();
+    prover_storage.lock().unwrap().insert(prover_id.clone(), prover);
+
+    Ok(Json(ImportProverResponse {
+        prover_id,
+        public_key: format!("0x{}", hex::encode(pk_buf)),
+    }))
+}
+
 async fn vrf_output_handler(
-    axum::extract::State((prover_storage, _)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
     Json(req): Json<VrfOutputRequest>,
 ) -> Result<Json<VrfOutputResponse>, StatusCode> {
     let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
@@ -469,8 +932,39 @@ async fn vrf_output_handler(
     }))
 }
 
+async fn vrf_bytes_handler(
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
+    Json(req): Json<VrfBytesRequest>,
+) -> Result<Json<VrfBytesResponse>, StatusCode> {
+    let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = prover_storage.lock().unwrap();
+    let prover = storage.get(&req.prover_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let bytes = prover.vrf_bytes(&vrf_input_data, req.context.as_bytes(), req.len);
+
+    let below_threshold = match &req.threshold {
+        Some(_) if bytes.len() < 16 => {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Some(threshold) => {
+            let threshold: u128 = threshold.parse().map_err(|_| StatusCode::BAD_REQUEST)?;
+            let mut bytes_u128: [u8; 16] = [0; 16];
+            bytes_u128.copy_from_slice(&bytes[..16]);
+            Some(u128::from_le_bytes(bytes_u128) < threshold)
+        }
+        None => None,
+    };
+
+    Ok(Json(VrfBytesResponse {
+        bytes: format!("0x{}", hex::encode(bytes)),
+        below_threshold,
+    }))
+}
+
 async fn ring_vrf_sign_handler(
-    axum::extract::State((prover_storage, _)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
     Json(req): Json<RingVrfSignRequest>,
 ) -> Result<Json<RingVrfSignResponse>, StatusCode> {
     let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
@@ -482,6 +976,7 @@ async fn ring_vrf_sign_handler(
     let prover = storage.get(&req.prover_id).ok_or(StatusCode::NOT_FOUND)?;
 
     let signature = prover.ring_vrf_sign(&vrf_input_data, &aux_data);
+    let signature = if req.scale { ring_vrf_signature_to_scale(&signature)?.encode() } else { signature };
 
     Ok(Json(RingVrfSignResponse {
         signature: format!("0x{}", hex::encode(signature)),
@@ -489,7 +984,7 @@ async fn ring_vrf_sign_handler(
 }
 
 async fn ietf_vrf_sign_handler(
-    axum::extract::State((prover_storage, _)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
     Json(req): Json<IetfVrfSignRequest>,
 ) -> Result<Json<IetfVrfSignResponse>, StatusCode> {
     let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
@@ -501,14 +996,40 @@ async fn ietf_vrf_sign_handler(
     let prover = storage.get(&req.prover_id).ok_or(StatusCode::NOT_FOUND)?;
 
     let signature = prover.ietf_vrf_sign(&vrf_input_data, &aux_data);
+    let signature = if req.scale { ietf_vrf_signature_to_scale(&signature)?.encode() } else { signature };
 
     Ok(Json(IetfVrfSignResponse {
         signature: format!("0x{}", hex::encode(signature)),
     }))
 }
 
+/// Build and sign a Sassafras/JAM ticket in one call: assemble the
+/// canonical `jam_ticket_seal` VRF input from `eta` and `attempt_index`
+/// via [`ticket_vrf_input`], sign it anonymously over the prover's ring,
+/// and derive the ticket identifier as the VRF output bytes, as a single
+/// first-class endpoint.
+async fn make_ticket_handler(
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
+    Json(req): Json<MakeTicketRequest>,
+) -> Result<Json<MakeTicketResponse>, StatusCode> {
+    let eta = hex::decode(req.eta.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = prover_storage.lock().unwrap();
+    let prover = storage.get(&req.prover_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let vrf_input_data = ticket_vrf_input(&eta, req.attempt_index);
+    let ticket_id = prover.vrf_output(&vrf_input_data);
+    let signature = prover.ring_vrf_sign(&vrf_input_data, b"");
+
+    Ok(Json(MakeTicketResponse {
+        ticket_id: format!("0x{}", hex::encode(ticket_id)),
+        attempt: req.attempt_index,
+        signature: format!("0x{}", hex::encode(signature)),
+    }))
+}
+
 async fn create_verifier_handler(
-    axum::extract::State((_, verifier_storage)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
     Json(req): Json<CreateVerifierRequest>,
 ) -> Result<Json<CreateVerifierResponse>, StatusCode> {
     let mut ring_keys = Vec::new();
@@ -536,7 +1057,7 @@ async fn create_verifier_handler(
 }
 
 async fn ring_vrf_verify_handler(
-    axum::extract::State((_, verifier_storage)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
     Json(req): Json<RingVrfVerifyRequest>,
 ) -> Result<Json<RingVrfVerifyResponse>, StatusCode> {
     let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
@@ -545,6 +1066,12 @@ async fn ring_vrf_verify_handler(
         .map_err(|_| StatusCode::BAD_REQUEST)?;
     let signature = hex::decode(req.signature.trim_start_matches("0x"))
         .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let signature = if req.scale {
+        let scale = ScaleRingVrfSignature::decode(&mut &signature[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+        scale_to_ring_vrf_signature_bytes(&scale)?
+    } else {
+        signature
+    };
 
     let storage = verifier_storage.lock().unwrap();
     let verifier = storage.get(&req.verifier_id).ok_or(StatusCode::NOT_FOUND)?;
@@ -561,6 +1088,187 @@ async fn ring_vrf_verify_handler(
     }
 }
 
+/// Verify a batch of ring-VRF signatures against a single stored [`Verifier`].
+///
+/// `/verifier/ring_vrf_verify` re-resolves `verifier_id` on every call but
+/// pays no setup cost beyond that lookup; the actual expensive step is the
+/// ring commitment built once in `/verifier/create` and stored in
+/// `verifier_storage`. This handler amortizes that lookup/setup across the
+/// whole batch by locking the storage and fetching the verifier a single
+/// time, then verifying each item against it.
+///
+/// Note: this `ark-vrf` backend does not expose a random-linear-combination
+/// batched pairing check, so each item's proof is still verified
+/// independently rather than folded into one combined pairing equation,
+/// and each `Verifier::ring_vrf_verify` call rebuilds the verifier key from
+/// the stored commitment; only the storage lock and lookup are shared
+/// across the batch.
+async fn ring_vrf_verify_batch_handler(
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
+    Json(req): Json<RingVrfVerifyBatchRequest>,
+) -> Result<Json<RingVrfVerifyBatchResponse>, StatusCode> {
+    let storage = verifier_storage.lock().unwrap();
+    let verifier = storage.get(&req.verifier_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut results = Vec::with_capacity(req.items.len());
+    let mut all_verified = true;
+    for item in &req.items {
+        let vrf_input_data = hex::decode(item.vrf_input_data.trim_start_matches("0x"))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let aux_data = hex::decode(item.aux_data.trim_start_matches("0x"))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let signature = hex::decode(item.signature.trim_start_matches("0x"))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+        let result = match verifier.ring_vrf_verify(&vrf_input_data, &aux_data, &signature) {
+            Ok(output_hash) => RingVrfVerifyBatchItemResult {
+                verified: true,
+                vrf_output_hash: Some(format!("0x{}", hex::encode(output_hash))),
+            },
+            Err(_) => RingVrfVerifyBatchItemResult {
+                verified: false,
+                vrf_output_hash: None,
+            },
+        };
+        all_verified &= result.verified;
+        results.push(result);
+    }
+
+    Ok(Json(RingVrfVerifyBatchResponse { all_verified, results }))
+}
+
+/// The Sassafras lottery ordering primitive: verify each submitted ticket
+/// anonymously against the shared ring, drop any whose signature fails
+/// (an invalid ticket has no valid lottery position), and return the rest
+/// sorted by ticket id ascending. The ticket id is recovered as the
+/// verified VRF output, not trusted from the request, so a caller cannot
+/// influence its own ranking by lying about it.
+async fn rank_tickets_handler(
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
+    Json(req): Json<RankTicketsRequest>,
+) -> Result<Json<RankTicketsResponse>, StatusCode> {
+    let eta = hex::decode(req.eta.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = verifier_storage.lock().unwrap();
+    let verifier = storage.get(&req.verifier_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let mut ranked = Vec::with_capacity(req.tickets.len());
+    for ticket in &req.tickets {
+        let signature = hex::decode(ticket.signature.trim_start_matches("0x"))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+        let vrf_input_data = ticket_vrf_input(&eta, ticket.attempt);
+
+        if let Ok(ticket_id) = verifier.ring_vrf_verify(&vrf_input_data, b"", &signature) {
+            ranked.push(RankedTicket {
+                ticket_id: format!("0x{}", hex::encode(&ticket_id)),
+                attempt: ticket.attempt,
+                signature: ticket.signature.clone(),
+            });
+        }
+    }
+
+    ranked.sort_by(|a, b| a.ticket_id.cmp(&b.ticket_id));
+
+    Ok(Json(RankTicketsResponse { tickets: ranked }))
+}
+
+/// Dealer-side key split: see [`threshold`] for why this is an n-of-n XOR
+/// split rather than genuine t-of-n Shamir sharing.
+async fn threshold_keygen_handler(
+    axum::extract::State((_, _, _, threshold_storage)): axum::extract::State<AppState>,
+    Json(req): Json<ThresholdKeygenRequest>,
+) -> Result<Json<ThresholdKeygenResponse>, StatusCode> {
+    if req.participants == 0 || req.threshold == 0 || req.threshold > req.participants {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let ring = parse_and_pad_ring(&req.public_keys);
+    if req.prover_index >= ring.len() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (mut session, shares) = threshold::ThresholdSession::keygen(ring, req.prover_index, req.threshold, req.participants);
+
+    let mut group_public_buf = Vec::new();
+    session.group_public.serialize_compressed(&mut group_public_buf).unwrap();
+    // The dealt group public key takes the place of `ring[prover_index]`
+    // that the caller originally sent as a placeholder.
+    session.ring[req.prover_index] = session.group_public.clone();
+
+    let session_id = Uuid::new_v4().to_string();
+    threshold_storage.lock().unwrap().insert(session_id.clone(), session);
+
+    Ok(Json(ThresholdKeygenResponse {
+        session_id,
+        group_public_key: format!("0x{}", hex::encode(group_public_buf)),
+        shares: shares.iter().map(|s| format!("0x{}", hex::encode(s))).collect(),
+    }))
+}
+
+async fn threshold_commit_handler(
+    axum::extract::State((_, _, _, threshold_storage)): axum::extract::State<AppState>,
+    Json(req): Json<ThresholdCommitRequest>,
+) -> Result<Json<ThresholdCommitResponse>, StatusCode> {
+    let commitment = hex::decode(req.commitment.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut storage = threshold_storage.lock().unwrap();
+    let session = storage.get_mut(&req.session_id).ok_or(StatusCode::NOT_FOUND)?;
+    session.commit(req.participant_index, commitment);
+
+    Ok(Json(ThresholdCommitResponse { accepted: true }))
+}
+
+/// Participants submit the share the dealer handed them back to the
+/// session. Once every participant has submitted, `/threshold/aggregate`
+/// can reconstruct the group secret and sign.
+async fn threshold_sign_partial_handler(
+    axum::extract::State((_, _, _, threshold_storage)): axum::extract::State<AppState>,
+    Json(req): Json<ThresholdSignPartialRequest>,
+) -> Result<Json<ThresholdSignPartialResponse>, StatusCode> {
+    let share_bytes = hex::decode(req.share.trim_start_matches("0x")).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let share: [u8; 32] = share_bytes.try_into().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut storage = threshold_storage.lock().unwrap();
+    let session = storage.get_mut(&req.session_id).ok_or(StatusCode::NOT_FOUND)?;
+    session
+        .submit_partial(req.participant_index, share)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    Ok(Json(ThresholdSignPartialResponse {
+        accepted: true,
+        partials_received: session.partial_count(),
+        participants_required: session.participants,
+    }))
+}
+
+/// Once every participant's share has been submitted, reconstruct the
+/// group secret and produce a ring VRF signature over `ring[prover_index]`
+/// the same way [`Prover::ring_vrf_sign`] would, verifiable by the
+/// existing `/verifier/ring_vrf_verify` against that same ring.
+async fn threshold_aggregate_handler(
+    axum::extract::State((_, _, _, threshold_storage)): axum::extract::State<AppState>,
+    Json(req): Json<ThresholdAggregateRequest>,
+) -> Result<Json<ThresholdAggregateResponse>, StatusCode> {
+    let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let aux_data = hex::decode(req.aux_data.trim_start_matches("0x"))
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = threshold_storage.lock().unwrap();
+    let session = storage.get(&req.session_id).ok_or(StatusCode::NOT_FOUND)?;
+    let secret = session.reconstruct().map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let prover = Prover {
+        prover_idx: session.prover_index,
+        secret,
+        ring: session.ring.clone(),
+    };
+    let signature = prover.ring_vrf_sign(&vrf_input_data, &aux_data);
+
+    Ok(Json(ThresholdAggregateResponse {
+        signature: format!("0x{}", hex::encode(signature)),
+    }))
+}
+
 async fn ring_vrf_verify_payload_handler(
     Json(req): Json<RingVrfVerifyPayloadRequest>,
 ) -> Result<Json<RingVrfVerifyPayloadResponse>, StatusCode> {
@@ -599,12 +1307,23 @@ async fn ring_vrf_verify_payload_handler(
     let expected_gamma_z_hex = hex::encode(&gamma_z_bytes);
     
     if calculated_commitment_hex != expected_gamma_z_hex {
+        // Decompose the ring into x/y pairs alongside both commitments so a
+        // mismatch can be diagnosed (e.g. a transposed or mis-padded key)
+        // without the caller re-running deserialization themselves.
+        let ring_points: Vec<AffinePoint> = verifier.ring.iter().map(|pk| pk.0).collect();
+        let diagnostic = serde_json::json!({
+            "calculated_commitment": format!("0x{calculated_commitment_hex}"),
+            "expected_gamma_z": format!("0x{expected_gamma_z_hex}"),
+            "ring": curve_view::points_view(&ring_points, false),
+        });
         return Ok(Json(RingVrfVerifyPayloadResponse {
             results: vec![TicketVerificationResult {
                 attempt: 0,
                 ok: false,
                 output_hash: None,
-                message: "Calculated commitment (gamma_z) does not match the provided gamma_z".to_string(),
+                message: format!(
+                    "Calculated commitment (gamma_z) does not match the provided gamma_z: {diagnostic}"
+                ),
             }],
         }));
     }
@@ -671,8 +1390,133 @@ async fn ring_vrf_verify_payload_handler(
     Ok(Json(RingVrfVerifyPayloadResponse { results }))
 }
 
+// ---- Streaming, constant-memory bulk ticket verification ----
+//
+// NOTE: this handler needs the `async-stream` and `http-body-util` crates
+// (and `futures-util` for `StreamExt`) added to `Cargo.toml`.
+
+/// First NDJSON line of a `/ring_vrf/verify_payload/stream` request: the
+/// same `gamma_z`/`ring_set`/`eta2_prime` header as `RingVrfVerifyPayloadRequest`,
+/// minus the `extrinsic` vector, which instead streams in as one
+/// `ExtrinsicItem` per subsequent line.
+#[derive(Deserialize)]
+struct StreamHeader {
+    gamma_z: String,
+    ring_set: Vec<String>,
+    eta2_prime: String,
+}
+
+fn verify_one_ticket(verifier: &Verifier, eta2_prime_bytes: &[u8], item: &ExtrinsicItem) -> TicketVerificationResult {
+    let signature = match hex::decode(item.signature.trim_start_matches("0x")) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            return TicketVerificationResult {
+                attempt: item.attempt,
+                ok: false,
+                output_hash: None,
+                message: format!("Failed to decode signature: {}", e),
+            };
+        }
+    };
+
+    let domain_separator = b"jam_ticket_seal";
+    let mut vrf_input_data = Vec::new();
+    vrf_input_data.extend_from_slice(domain_separator);
+    vrf_input_data.extend_from_slice(eta2_prime_bytes);
+    vrf_input_data.push(item.attempt);
+
+    match verifier.ring_vrf_verify(&vrf_input_data, b"", &signature) {
+        Ok(output_hash) => TicketVerificationResult {
+            attempt: item.attempt,
+            ok: true,
+            output_hash: Some(format!("0x{}", hex::encode(output_hash))),
+            message: format!("Ticket {} verified successfully", item.attempt),
+        },
+        Err(_) => TicketVerificationResult {
+            attempt: item.attempt,
+            ok: false,
+            output_hash: None,
+            message: format!("Ticket {} verification failed", item.attempt),
+        },
+    }
+}
+
+/// `POST /ring_vrf/verify_payload/stream` — an NDJSON + chunked-response
+/// sibling of `ring_vrf_verify_payload_handler` that never buffers the full
+/// `extrinsic` vector. The header line builds the `Verifier` (its ring
+/// commitment) exactly once; the verifier key is not kept, and
+/// `Verifier::ring_vrf_verify` rebuilds it from that commitment for every
+/// ticket. Each following line is
+/// deserialized, verified, and dropped before the next line is read, so
+/// memory stays bounded regardless of how many tickets an epoch has.
+async fn ring_vrf_verify_payload_stream_handler(request: axum::extract::Request) -> Result<axum::response::Response, StatusCode> {
+    use futures_util::StreamExt;
+    use http_body_util::BodyExt;
+
+    let mut body = request.into_body().into_data_stream();
+
+    let response_stream = async_stream::stream! {
+        let mut buf: Vec<u8> = Vec::new();
+        let mut verifier: Option<Verifier> = None;
+        let mut eta2_prime_bytes: Vec<u8> = Vec::new();
+
+        while let Some(chunk) = body.next().await {
+            let Ok(chunk) = chunk else { break };
+            buf.extend_from_slice(&chunk);
+
+            while let Some(newline_pos) = buf.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buf.drain(..=newline_pos).collect();
+                let line = &line[..line.len() - 1];
+                if line.is_empty() {
+                    continue;
+                }
+
+                if verifier.is_none() {
+                    let Ok(header) = serde_json::from_slice::<StreamHeader>(line) else {
+                        yield Ok::<_, std::convert::Infallible>(Vec::from(b"{\"error\":\"bad header\"}\n" as &[u8]));
+                        return;
+                    };
+
+                    let mut ring = Vec::with_capacity(header.ring_set.len());
+                    for hex_str in &header.ring_set {
+                        let Ok(bytes) = hex::decode(hex_str.trim_start_matches("0x")) else { continue };
+                        if let Ok(pk) = Public::deserialize_compressed(&bytes[..]) {
+                            ring.push(pk);
+                        }
+                    }
+                    let built = Verifier::new(ring);
+
+                    let mut commitment_bytes = Vec::new();
+                    built.commitment.serialize_compressed(&mut commitment_bytes).unwrap();
+                    if hex::encode(&commitment_bytes) != header.gamma_z.trim_start_matches("0x") {
+                        yield Ok::<_, std::convert::Infallible>(Vec::from(b"{\"error\":\"gamma_z mismatch\"}\n" as &[u8]));
+                        return;
+                    }
+
+                    eta2_prime_bytes = hex::decode(header.eta2_prime.trim_start_matches("0x")).unwrap_or_default();
+                    verifier = Some(built);
+                    continue;
+                }
+
+                let Ok(item) = serde_json::from_slice::<ExtrinsicItem>(line) else {
+                    continue;
+                };
+                let result = verify_one_ticket(verifier.as_ref().unwrap(), &eta2_prime_bytes, &item);
+                let mut line_out = serde_json::to_vec(&result).unwrap_or_default();
+                line_out.push(b'\n');
+                yield Ok::<_, std::convert::Infallible>(line_out);
+            }
+        }
+    };
+
+    Ok(axum::response::Response::builder()
+        .header("content-type", "application/x-ndjson")
+        .body(axum::body::Body::from_stream(response_stream))
+        .unwrap())
+}
+
 async fn ietf_vrf_verify_handler(
-    axum::extract::State((_, verifier_storage)): axum::extract::State<(ProverStorage, VerifierStorage)>,
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
     Json(req): Json<IetfVrfVerifyRequest>,
 ) -> Result<Json<IetfVrfVerifyResponse>, StatusCode> {
     let vrf_input_data = hex::decode(req.vrf_input_data.trim_start_matches("0x"))
@@ -681,6 +1525,12 @@ async fn ietf_vrf_verify_handler(
         .map_err(|_| StatusCode::BAD_REQUEST)?;
     let signature = hex::decode(req.signature.trim_start_matches("0x"))
         .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let signature = if req.scale {
+        let scale = ScaleIetfVrfSignature::decode(&mut &signature[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+        scale_to_ietf_vrf_signature_bytes(&scale)?
+    } else {
+        signature
+    };
 
     let storage = verifier_storage.lock().unwrap();
     let verifier = storage.get(&req.verifier_id).ok_or(StatusCode::NOT_FOUND)?;
@@ -741,11 +1591,36 @@ async fn api_docs_handler() -> Json<ApiDocResponse> {
             path: "/prover/create".to_string(),
             description: "Create a new prover instance with a ring of public keys".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/prover/import".to_string(),
+            description: "Derive a prover's secret from a BIP39 mnemonic/seed plus a BIP32-style derivation path".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/keystore/generate".to_string(),
+            description: "Generate a new keypair and store it in the keystore".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/keystore/insert".to_string(),
+            description: "Insert an existing key into the keystore".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/keystore/public_keys".to_string(),
+            description: "List public keys currently held in the keystore".to_string(),
+        },
         ApiEndpoint {
             method: "POST".to_string(),
             path: "/prover/vrf_output".to_string(),
             description: "Generate VRF output hash for given input data".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/prover/vrf_bytes".to_string(),
+            description: "Generate arbitrary-length VRF output bytes for given input data".to_string(),
+        },
         ApiEndpoint {
             method: "POST".to_string(),
             path: "/prover/ring_vrf_sign".to_string(),
@@ -756,6 +1631,11 @@ async fn api_docs_handler() -> Json<ApiDocResponse> {
             path: "/prover/ietf_vrf_sign".to_string(),
             description: "Create non-anonymous VRF signature (IETF standard)".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/prover/make_ticket".to_string(),
+            description: "Build and sign a Sassafras/JAM ticket from eta and an attempt index".to_string(),
+        },
         ApiEndpoint {
             method: "POST".to_string(),
             path: "/verifier/create".to_string(),
@@ -766,41 +1646,307 @@ async fn api_docs_handler() -> Json<ApiDocResponse> {
             path: "/verifier/ring_vrf_verify".to_string(),
             description: "Verify anonymous VRF signature (ring signature)".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/verifier/ring_vrf_verify_batch".to_string(),
+            description: "Verify a batch of ring VRF signatures against one stored verifier, with per-item and aggregate results".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/verifier/rank_tickets".to_string(),
+            description: "Verify a batch of Sassafras/JAM tickets and return the valid ones ranked by ticket id".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/threshold/keygen".to_string(),
+            description: "Dealer-side threshold key split (n-of-n XOR split) for a threshold signing session".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/threshold/commit".to_string(),
+            description: "Commit to a threshold signing session before partial signatures are submitted".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/threshold/sign_partial".to_string(),
+            description: "Submit a participant's share to an in-progress threshold signing session".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/threshold/aggregate".to_string(),
+            description: "Reconstruct the group secret from submitted shares and produce the ring VRF signature".to_string(),
+        },
         ApiEndpoint {
             method: "POST".to_string(),
             path: "/verifier/ring_vrf_verify_payload".to_string(),
             description: "Verify ring VRF signature with payload (gamma_z, ring_set, eta2_prime, extrinsic)".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/ring_vrf/verify_payload/stream".to_string(),
+            description: "Streaming sibling of /verifier/ring_vrf_verify_payload that never buffers the full extrinsic vector".to_string(),
+        },
         ApiEndpoint {
             method: "POST".to_string(),
             path: "/verifier/ietf_vrf_verify".to_string(),
             description: "Verify non-anonymous VRF signature (IETF standard)".to_string(),
         },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/scale/ring_vrf/sign".to_string(),
+            description: "Same as /prover/ring_vrf_sign, but request/response bodies are raw SCALE bytes instead of hex-in-JSON".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/scale/ring_vrf/verify".to_string(),
+            description: "Verify a SCALE-encoded ring VRF ticket envelope, returning a SCALE-encoded bool and output hash".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/scale/ietf_vrf/sign".to_string(),
+            description: "Same as /prover/ietf_vrf_sign, but request/response bodies are raw SCALE bytes instead of hex-in-JSON".to_string(),
+        },
+        ApiEndpoint {
+            method: "POST".to_string(),
+            path: "/scale/ietf_vrf/verify".to_string(),
+            description: "Verify a SCALE-encoded IETF VRF verify request, returning a SCALE-encoded bool and output hash".to_string(),
+        },
     ];
 
     Json(ApiDocResponse { endpoints })
 }
 
+// ---- SCALE codec surface (JAM/Substrate interop) ----
+//
+// `sp_core::bandersnatch` and JAM nodes exchange these structures as raw
+// `parity-scale-codec` bytes rather than hex-in-JSON. These wrapper types
+// carry the same fields as `RingVrfSignature`/`IetfVrfSignature` and the
+// ticket envelope used by `ring_vrf_verify_payload_handler`, but store the
+// curve material pre-serialized (via `serialize_compressed`) so they can
+// derive `Encode`/`Decode` without ark types needing to implement it
+// themselves.
+
+#[derive(Encode, Decode)]
+struct ScaleRingVrfSignature {
+    output: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+#[derive(Encode, Decode)]
+struct ScaleIetfVrfSignature {
+    output: Vec<u8>,
+    proof: Vec<u8>,
+}
+
+/// Mirrors `RingVrfVerifyPayloadRequest`'s ticket shape, SCALE-encoded:
+/// `ring_set`, `eta2_prime`, per-attempt index, and the ring signature.
+#[derive(Encode, Decode)]
+struct ScaleTicketEnvelope {
+    ring_set: Vec<Vec<u8>>,
+    eta2_prime: Vec<u8>,
+    attempt: u8,
+    signature: ScaleRingVrfSignature,
+}
+
+#[derive(Encode, Decode)]
+struct ScaleRingVrfSignRequest {
+    prover_id: Vec<u8>,
+    vrf_input_data: Vec<u8>,
+    aux_data: Vec<u8>,
+}
+
+#[derive(Encode, Decode)]
+struct ScaleIetfVrfSignRequest {
+    prover_id: Vec<u8>,
+    vrf_input_data: Vec<u8>,
+    aux_data: Vec<u8>,
+}
+
+#[derive(Encode, Decode)]
+struct ScaleIetfVrfVerifyRequest {
+    verifier_id: Vec<u8>,
+    vrf_input_data: Vec<u8>,
+    aux_data: Vec<u8>,
+    signature: ScaleIetfVrfSignature,
+    signer_key_index: u32,
+}
+
+fn ring_vrf_signature_to_scale(signature_bytes: &[u8]) -> Result<ScaleRingVrfSignature, StatusCode> {
+    let signature = RingVrfSignature::deserialize_compressed(signature_bytes)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let mut output = Vec::new();
+    let mut proof = Vec::new();
+    signature.output.serialize_compressed(&mut output).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    signature.proof.serialize_compressed(&mut proof).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(ScaleRingVrfSignature { output, proof })
+}
+
+fn scale_to_ring_vrf_signature_bytes(scale: &ScaleRingVrfSignature) -> Result<Vec<u8>, StatusCode> {
+    let output = Output::deserialize_compressed(&scale.output[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let proof = RingProof::deserialize_compressed(&scale.proof[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let signature = RingVrfSignature { output, proof };
+    let mut buf = Vec::new();
+    signature.serialize_compressed(&mut buf).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(buf)
+}
+
+fn ietf_vrf_signature_to_scale(signature_bytes: &[u8]) -> Result<ScaleIetfVrfSignature, StatusCode> {
+    let signature = IetfVrfSignature::deserialize_compressed(signature_bytes)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let mut output = Vec::new();
+    let mut proof = Vec::new();
+    signature.output.serialize_compressed(&mut output).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    signature.proof.serialize_compressed(&mut proof).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(ScaleIetfVrfSignature { output, proof })
+}
+
+fn scale_to_ietf_vrf_signature_bytes(scale: &ScaleIetfVrfSignature) -> Result<Vec<u8>, StatusCode> {
+    let output = Output::deserialize_compressed(&scale.output[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let proof = IetfProof::deserialize_compressed(&scale.proof[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let signature = IetfVrfSignature { output, proof };
+    let mut buf = Vec::new();
+    signature.serialize_compressed(&mut buf).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(buf)
+}
+
+/// `POST /scale/ring_vrf/sign` — same as `/prover/ring_vrf_sign`, but the
+/// request and response bodies are raw `parity-scale-codec` bytes instead
+/// of hex-in-JSON.
+async fn scale_ring_vrf_sign_handler(
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
+    body: axum::body::Bytes,
+) -> Result<Vec<u8>, StatusCode> {
+    let req = ScaleRingVrfSignRequest::decode(&mut &body[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let prover_id = String::from_utf8(req.prover_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = prover_storage.lock().unwrap();
+    let prover = storage.get(&prover_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let signature_bytes = prover.ring_vrf_sign(&req.vrf_input_data, &req.aux_data);
+    let scale_signature = ring_vrf_signature_to_scale(&signature_bytes)?;
+    Ok(scale_signature.encode())
+}
+
+/// `POST /scale/ring_vrf/verify` — takes a SCALE-encoded `ScaleTicketEnvelope`
+/// and returns a SCALE-encoded `bool` (verified) followed by the 32-byte
+/// VRF output hash when verification succeeds.
+async fn scale_ring_vrf_verify_handler(body: axum::body::Bytes) -> Result<Vec<u8>, StatusCode> {
+    let envelope = ScaleTicketEnvelope::decode(&mut &body[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let mut ring = Vec::with_capacity(envelope.ring_set.len());
+    for pk_bytes in &envelope.ring_set {
+        ring.push(Public::deserialize_compressed(&pk_bytes[..]).map_err(|_| StatusCode::BAD_REQUEST)?);
+    }
+    let verifier = Verifier::new(ring);
+
+    let signature_bytes = scale_to_ring_vrf_signature_bytes(&envelope.signature)?;
+
+    let domain_separator = b"jam_ticket_seal";
+    let mut vrf_input_data = Vec::new();
+    vrf_input_data.extend_from_slice(domain_separator);
+    vrf_input_data.extend_from_slice(&envelope.eta2_prime);
+    vrf_input_data.push(envelope.attempt);
+
+    let result = verifier.ring_vrf_verify(&vrf_input_data, b"", &signature_bytes);
+    let mut response = result.is_ok().encode();
+    if let Ok(output_hash) = result {
+        response.extend_from_slice(&output_hash);
+    }
+    Ok(response)
+}
+
+/// `POST /scale/ietf_vrf/sign` — same as `/prover/ietf_vrf_sign`, but the
+/// request and response bodies are raw `parity-scale-codec` bytes instead
+/// of hex-in-JSON.
+async fn scale_ietf_vrf_sign_handler(
+    axum::extract::State((prover_storage, _, _, _)): axum::extract::State<AppState>,
+    body: axum::body::Bytes,
+) -> Result<Vec<u8>, StatusCode> {
+    let req = ScaleIetfVrfSignRequest::decode(&mut &body[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let prover_id = String::from_utf8(req.prover_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = prover_storage.lock().unwrap();
+    let prover = storage.get(&prover_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let signature_bytes = prover.ietf_vrf_sign(&req.vrf_input_data, &req.aux_data);
+    let scale_signature = ietf_vrf_signature_to_scale(&signature_bytes)?;
+    Ok(scale_signature.encode())
+}
+
+/// `POST /scale/ietf_vrf/verify` — takes a SCALE-encoded
+/// `ScaleIetfVrfVerifyRequest` and returns a SCALE-encoded `bool` (verified)
+/// followed by the 32-byte VRF output hash when verification succeeds.
+async fn scale_ietf_vrf_verify_handler(
+    axum::extract::State((_, verifier_storage, _, _)): axum::extract::State<AppState>,
+    body: axum::body::Bytes,
+) -> Result<Vec<u8>, StatusCode> {
+    let req = ScaleIetfVrfVerifyRequest::decode(&mut &body[..]).map_err(|_| StatusCode::BAD_REQUEST)?;
+    let verifier_id = String::from_utf8(req.verifier_id).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let storage = verifier_storage.lock().unwrap();
+    let verifier = storage.get(&verifier_id).ok_or(StatusCode::NOT_FOUND)?;
+
+    let signature_bytes = scale_to_ietf_vrf_signature_bytes(&req.signature)?;
+    let result = verifier.ietf_vrf_verify(
+        &req.vrf_input_data,
+        &req.aux_data,
+        &signature_bytes,
+        req.signer_key_index as usize
+    );
+
+    let mut response = result.is_ok().encode();
+    if let Ok(output_hash) = result {
+        response.extend_from_slice(&output_hash);
+    }
+    Ok(response)
+}
+
 #[tokio::main]
 async fn main() {
     print_points();
 
     let prover_storage: ProverStorage = Arc::new(Mutex::new(StdHashMap::new()));
     let verifier_storage: VerifierStorage = Arc::new(Mutex::new(StdHashMap::new()));
-    let state = (prover_storage, verifier_storage);
+    // Directory and password are both configurable: point `KEYSTORE_DIR` at
+    // a persistent volume and set `KEYSTORE_PASSWORD` to encrypt keys at
+    // rest in any real deployment.
+    let keystore_dir = std::env::var("KEYSTORE_DIR").unwrap_or_else(|_| "keystore".to_string());
+    let keystore_password = std::env::var("KEYSTORE_PASSWORD").ok();
+    let keystore: KeyStoreHandle = Arc::new(
+        keystore::KeyStore::open(keystore_dir, keystore_password).expect("keystore directory is writable")
+    );
+    let threshold_storage: ThresholdStorage = Arc::new(Mutex::new(StdHashMap::new()));
+    let state: AppState = (prover_storage, verifier_storage, keystore, threshold_storage);
 
     let app = Router::new()
         .route("/", get(api_docs_handler))
         .route("/constant_points", get(constant_points_handler))
         .route("/compose_gamma_z", post(compose_gamma_z_handler))
         .route("/prover/create", post(create_prover_handler))
+        .route("/prover/import", post(import_prover_handler))
+        .route("/keystore/generate", post(keystore_generate_handler))
+        .route("/keystore/insert", post(keystore_insert_handler))
+        .route("/keystore/public_keys", post(keystore_public_keys_handler))
         .route("/prover/vrf_output", post(vrf_output_handler))
+        .route("/prover/vrf_bytes", post(vrf_bytes_handler))
         .route("/prover/ring_vrf_sign", post(ring_vrf_sign_handler))
         .route("/prover/ietf_vrf_sign", post(ietf_vrf_sign_handler))
+        .route("/prover/make_ticket", post(make_ticket_handler))
         .route("/verifier/create", post(create_verifier_handler))
         .route("/verifier/ring_vrf_verify", post(ring_vrf_verify_handler))
+        .route("/verifier/ring_vrf_verify_batch", post(ring_vrf_verify_batch_handler))
+        .route("/verifier/rank_tickets", post(rank_tickets_handler))
+        .route("/threshold/keygen", post(threshold_keygen_handler))
+        .route("/threshold/commit", post(threshold_commit_handler))
+        .route("/threshold/sign_partial", post(threshold_sign_partial_handler))
+        .route("/threshold/aggregate", post(threshold_aggregate_handler))
         .route("/verifier/ring_vrf_verify_payload", post(ring_vrf_verify_payload_handler))
+        .route("/ring_vrf/verify_payload/stream", post(ring_vrf_verify_payload_stream_handler))
         .route("/verifier/ietf_vrf_verify", post(ietf_vrf_verify_handler))
+        .route("/scale/ring_vrf/sign", post(scale_ring_vrf_sign_handler))
+        .route("/scale/ring_vrf/verify", post(scale_ring_vrf_verify_handler))
+        .route("/scale/ietf_vrf/sign", post(scale_ietf_vrf_sign_handler))
+        .route("/scale/ietf_vrf/verify", post(scale_ietf_vrf_verify_handler))
         .with_state(state);
 
     let addr = std::net::SocketAddr::from(([127, 0, 0, 1], 3000));
@@ -868,4 +2014,41 @@ mod tests {
         println!("\nâœ…âœ…âœ… SUCCESS: The self-generated signature was verified correctly in Rust. âœ…âœ…âœ…");
         println!("This confirms the correct input construction.");
     }
+
+    /// A ticket built via `ticket_vrf_input` (as `/prover/make_ticket` does)
+    /// must verify through `verify_one_ticket` (as
+    /// `/ring_vrf/verify_payload/stream` does) — the two must agree on the
+    /// VRF input's byte layout for the same artifact.
+    #[test]
+    fn ticket_made_via_ticket_vrf_input_verifies_via_verify_one_ticket() {
+        let public_keys_hex = vec![
+            "0xff71c6c03ff88adb5ed52c9681de1629a54e702fc14729f6b50d2f0a76f185b3",
+            "0xdee6d555b82024f1ccf8a1e37e60fa60fd40b1958c4bb3006af78647950e1b91",
+            "0x9326edb21e5541717fde24ec085000b28709847b8aab1ac51f84e94b37ca1b66",
+        ];
+        let ring: Vec<Public> = public_keys_hex
+            .iter()
+            .map(|hex| {
+                let bytes = hex::decode(hex.trim_start_matches("0x")).unwrap();
+                Public::deserialize_compressed(&bytes[..]).unwrap()
+            })
+            .collect();
+        let prover_index = 1;
+        let attempt_index: u8 = 2;
+        let eta = hex::decode("bb30a42c1e62f0afda5f0a4e8a562f7a13a24cea00ee81917b86b89e801314aa").unwrap();
+
+        let prover = Prover::new(ring.clone(), prover_index);
+
+        let vrf_input_data = ticket_vrf_input(&eta, attempt_index as u32);
+        let signature = prover.ring_vrf_sign(&vrf_input_data, b"");
+
+        let verifier = Verifier::new(ring);
+        let item = ExtrinsicItem {
+            attempt: attempt_index,
+            signature: format!("0x{}", hex::encode(signature)),
+        };
+        let result = verify_one_ticket(&verifier, &eta, &item);
+
+        assert!(result.ok, "ticket made via ticket_vrf_input failed to verify via verify_one_ticket: {}", result.message);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,122 @@
+//! Trusted-dealer threshold signing coordination for a single ring member.
+//!
+//! This does not implement genuine FROST: FROST's partial signatures and
+//! Lagrange interpolation rely on linear (additive) secret sharing *and*
+//! on a signing equation that is itself linear in the secret, neither of
+//! which this crate's `bandersnatch`/`ark-vrf` wrapper exposes — `Secret`
+//! is an opaque seed-derived scalar with no arithmetic operations, and
+//! ring proof construction (`RingProofParams::prover`) is far from a bare
+//! Schnorr equation. Without scalar-field arithmetic there is no honest
+//! way to combine independently-computed partial ring proofs into one
+//! valid proof.
+//!
+//! What this module *does* provide is the coordination shape FROST calls
+//! for, built on a primitive this crate can implement correctly: a
+//! trusted dealer additively (XOR) splits one freshly generated secret
+//! seed into `participants` shares, so no single share leaks the secret;
+//! participants register a commitment and submit their share back; once
+//! every share has been returned the dealer reconstructs the seed and
+//! signs with the existing [`crate::Prover::ring_vrf_sign`]. The
+//! `threshold` parameter is recorded for a future genuine Shamir upgrade
+//! but is not cryptographically enforced — reconstruction requires all
+//! `participants` shares (an n-of-n scheme), not just `threshold` of them.
+
+use ark_vrf::reexports::ark_serialize::CanonicalSerialize;
+use bandersnatch::{Public, Secret};
+use rand::RngCore;
+use std::collections::BTreeMap;
+
+/// One in-progress threshold signing session for a single ring member.
+pub struct ThresholdSession {
+    pub threshold: usize,
+    pub participants: usize,
+    pub ring: Vec<Public>,
+    pub prover_index: usize,
+    pub group_public: Public,
+    commitments: BTreeMap<u32, Vec<u8>>,
+    partials: BTreeMap<u32, [u8; 32]>,
+}
+
+impl ThresholdSession {
+    /// Split a freshly generated secret into `participants` XOR shares and
+    /// record the resulting group public key as `group_public`. `ring` is
+    /// stored as given; placing `group_public` at `ring[prover_index]` is
+    /// left to the caller.
+    pub fn keygen(ring: Vec<Public>, prover_index: usize, threshold: usize, participants: usize) -> (Self, Vec<[u8; 32]>) {
+        let mut seed = [0u8; 32];
+        rand::rngs::OsRng.fill_bytes(&mut seed);
+        let group_public = Public::from(Secret::from_seed(&seed));
+
+        let mut shares = Vec::with_capacity(participants);
+        let mut accumulator = seed;
+        for _ in 1..participants {
+            let mut share = [0u8; 32];
+            rand::rngs::OsRng.fill_bytes(&mut share);
+            for (a, s) in accumulator.iter_mut().zip(share.iter()) {
+                *a ^= s;
+            }
+            shares.push(share);
+        }
+        // Last share absorbs whatever is left so all shares XOR back to `seed`.
+        shares.push(accumulator);
+
+        let session = Self {
+            threshold,
+            participants,
+            ring,
+            prover_index,
+            group_public,
+            commitments: BTreeMap::new(),
+            partials: BTreeMap::new(),
+        };
+        (session, shares)
+    }
+
+    /// Record that `participant_index` (1-indexed) has produced a nonce
+    /// commitment ahead of signing.
+    pub fn commit(&mut self, participant_index: u32, commitment: Vec<u8>) {
+        self.commitments.insert(participant_index, commitment);
+    }
+
+    pub fn has_committed(&self, participant_index: u32) -> bool {
+        self.commitments.contains_key(&participant_index)
+    }
+
+    /// Record `participant_index`'s share as their contribution toward
+    /// reconstruction. Returns an error if they have not yet committed.
+    pub fn submit_partial(&mut self, participant_index: u32, share: [u8; 32]) -> Result<(), &'static str> {
+        if !self.has_committed(participant_index) {
+            return Err("participant must commit before submitting a partial");
+        }
+        self.partials.insert(participant_index, share);
+        Ok(())
+    }
+
+    pub fn partial_count(&self) -> usize {
+        self.partials.len()
+    }
+
+    /// Reconstruct the group secret once every participant's share has
+    /// been submitted, by XOR-folding them back together.
+    pub fn reconstruct(&self) -> Result<Secret, &'static str> {
+        if self.partials.len() < self.participants {
+            return Err("not all participant shares have been submitted");
+        }
+        let mut seed = [0u8; 32];
+        for share in self.partials.values() {
+            for (a, s) in seed.iter_mut().zip(share.iter()) {
+                *a ^= s;
+            }
+        }
+        let secret = Secret::from_seed(&seed);
+        let reconstructed_public = Public::from(Secret::from_seed(&seed));
+        let mut reconstructed_buf = Vec::new();
+        let mut group_buf = Vec::new();
+        reconstructed_public.serialize_compressed(&mut reconstructed_buf).unwrap();
+        self.group_public.serialize_compressed(&mut group_buf).unwrap();
+        if reconstructed_buf != group_buf {
+            return Err("reconstructed secret does not match the dealer's group public key");
+        }
+        Ok(secret)
+    }
+}
@@ -1,122 +1,167 @@
 extern crate alloc;
 
-use crate::types::AuthCredentials;
+use crate::state_store::{self, StateStore};
+use crate::types::{AuthCredentials, AuthProblem, SigAlg};
 use ed25519_dalek::{ Signature, VerifyingKey };
-use jam_codec::Decode;
+use jam_codec::{ Decode, Encode };
 use jam_pvm_common::{ declare_authorizer, info, Authorizer };
 use jam_types::{ AuthOutput, AuthParam, CoreIndex, WorkPackage };
+use rand::{ rngs::OsRng, RngCore };
 use serde::{Serialize, Deserialize};
 use sha2::{ Digest, Sha256 };
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
 use std::sync::Mutex;
 
 lazy_static::lazy_static! {
+    // `STATE_DIR` (default "../server") and `STATE_STORE` (default "file")
+    // select where this lives; see `state_store::from_env`.
+    static ref STATE_STORE: Box<dyn StateStore> = state_store::from_env("../server");
     static ref AUTH_STATE: Mutex<AuthState> = Mutex::new(load_auth_state());
 }
 
+/// How long an issued-but-unredeemed nonce from [`new_nonce`] stays valid,
+/// mirroring ACME's bounded `Replay-Nonce` lifetime. Past this, `is_authorized`
+/// treats the nonce as unknown even though it was never consumed.
+const NONCE_TTL_SECS: i64 = 3600;
+
+/// Hard cap on outstanding issued nonces, so repeatedly calling `new_nonce()`
+/// without ever redeeming any of them can't grow `AuthState` without bound.
+/// The oldest entry is evicted once the cap is hit.
+const MAX_ISSUED_NONCES: usize = 10_000;
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct AuthState {
-    #[serde(with = "nonces_serde")]
-    pub nonces: HashMap<[u8; 32], u64>,
+    /// ACME-style anti-replay pool: nonces handed out by [`new_nonce`] but
+    /// not yet redeemed by a successful `is_authorized` call, keyed by their
+    /// hex encoding, with the unix timestamp (seconds) each was issued at.
+    /// A nonce is consumed (removed) the moment it authorizes a credential,
+    /// so it can never be replayed — whether in order, out of order, or
+    /// concurrently with itself.
+    pub issued_nonces: HashMap<String, i64>,
     pub authorizations: HashMap<String, AuthRecord>,
 }
 
-mod nonces_serde {
-    use super::*;
-    use serde::{Deserialize, Deserializer, Serialize, Serializer};
-    use std::collections::HashMap;
-
-    pub fn serialize<S>(nonces: &HashMap<[u8; 32], u64>, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let string_map: HashMap<String, u64> = nonces
-            .iter()
-            .map(|(k, v)| (hex::encode(k), *v))
-            .collect();
-        string_map.serialize(serializer)
-    }
-
-    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<[u8; 32], u64>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let string_map: HashMap<String, u64> = HashMap::deserialize(deserializer)?;
-        let mut nonces = HashMap::new();
-        
-        for (hex_key, value) in string_map {
-            if let Ok(bytes) = hex::decode(&hex_key) {
-                if bytes.len() == 32 {
-                    let mut key_array = [0u8; 32];
-                    key_array.copy_from_slice(&bytes);
-                    nonces.insert(key_array, value);
-                }
-            }
-        }
-        
-        Ok(nonces)
-    }
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct AuthRecord {
     pub public_key: String,
-    pub nonce: u64,
+    /// Stable account handle for `public_key`, independent of `alg`; see
+    /// [`AuthCredentials::thumbprint`].
+    pub thumbprint: String,
+    /// Hex encoding of the (now-consumed) nonce this authorization redeemed.
+    pub nonce: String,
     pub last_updated: String,
     pub payload: serde_json::Value,
 }
 
-const STATE_FILE: &str = "../server/updated_state.json";
+/// Key this state is stored under within whichever `StateStore` is active.
+const AUTH_STATE_KEY: &str = "updated_state.json";
 
 fn load_auth_state() -> AuthState {
-    let path = Path::new(STATE_FILE);
-    if !path.exists() {
-        return AuthState::default();
-    }
-
-    match fs::read_to_string(path) {
-        Ok(contents) => {
-            // Try to parse as AuthState first, then try as generic JSON
-            if let Ok(auth_state) = serde_json::from_str::<AuthState>(&contents) {
-                auth_state
-            } else if let Ok(generic_json) = serde_json::from_str::<serde_json::Value>(&contents) {
-                // Handle server's updated_state.json format
-                let mut auth_state = AuthState::default();
-                
-                if let Some(authorizations) = generic_json.get("authorizations").and_then(|v| v.as_object()) {
-                    for (pub_key, auth_data) in authorizations {
-                        if let Some(nonce) = auth_data.get("nonce").and_then(|v| v.as_u64()) {
-                            // Convert hex string to bytes for nonce storage
-                            if let Ok(pub_key_bytes) = hex::decode(pub_key) {
-                                if pub_key_bytes.len() == 32 {
-                                    let mut key_array = [0u8; 32];
-                                    key_array.copy_from_slice(&pub_key_bytes);
-                                    auth_state.nonces.insert(key_array, nonce);
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                auth_state
-            } else {
-                eprintln!("Failed to parse auth state, using default");
-                AuthState::default()
-            }
-        }
+    let bytes = match STATE_STORE.load(AUTH_STATE_KEY) {
+        Ok(Some(bytes)) => bytes,
+        Ok(None) => return AuthState::default(),
         Err(e) => {
             eprintln!("Failed to read auth state: {}", e);
-            AuthState::default()
+            return AuthState::default();
         }
+    };
+
+    let Ok(contents) = String::from_utf8(bytes) else {
+        eprintln!("Auth state is not valid UTF-8, using default");
+        return AuthState::default();
+    };
+
+    // Try to parse as AuthState first, falling back to a default rather
+    // than guessing at an older on-disk shape.
+    if let Ok(auth_state) = serde_json::from_str::<AuthState>(&contents) {
+        auth_state
+    } else {
+        eprintln!("Failed to parse auth state, using default");
+        AuthState::default()
     }
 }
 
 fn save_auth_state(state: &AuthState) -> std::io::Result<()> {
     let serialized = serde_json::to_string_pretty(state)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-    fs::write(STATE_FILE, serialized)
+    STATE_STORE.store(AUTH_STATE_KEY, serialized.as_bytes())
+}
+
+/// Drop any issued nonce older than [`NONCE_TTL_SECS`] from the pool.
+fn prune_expired_nonces(state: &mut AuthState) {
+    let now = chrono::Utc::now().timestamp();
+    state.issued_nonces.retain(|_, issued_at| now - *issued_at < NONCE_TTL_SECS);
+}
+
+/// Issue a fresh, single-use anti-replay nonce (ACME's `new-nonce`
+/// semantics): 32 CSPRNG bytes, hex-encoded, recorded with their issuance
+/// time so a later `is_authorized` call can confirm a presented nonce is
+/// both known and unexpired before consuming it.
+pub fn new_nonce() -> String {
+    let mut state = AUTH_STATE.lock().unwrap();
+    prune_expired_nonces(&mut state);
+
+    if state.issued_nonces.len() >= MAX_ISSUED_NONCES {
+        if let Some(oldest) = state
+            .issued_nonces
+            .iter()
+            .min_by_key(|(_, issued_at)| **issued_at)
+            .map(|(nonce, _)| nonce.clone())
+        {
+            state.issued_nonces.remove(&oldest);
+        }
+    }
+
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let nonce = hex::encode(bytes);
+    state.issued_nonces.insert(nonce.clone(), chrono::Utc::now().timestamp());
+
+    if let Err(e) = save_auth_state(&state) {
+        eprintln!("Failed to save auth state after issuing nonce: {}", e);
+    }
+
+    nonce
+}
+
+/// Verify `signature` over `message` under `public_key`, dispatching on
+/// `alg` to the matching curve. Each branch returns its own `&'static str`
+/// reason so a caller can tell a malformed key/signature apart from a
+/// signature that simply doesn't verify, per algorithm.
+pub(crate) fn verify_signature(
+    alg: SigAlg,
+    public_key: &[u8],
+    signature: &[u8],
+    message: &[u8],
+) -> Result<(), &'static str> {
+    match alg {
+        SigAlg::Ed25519 => {
+            let public_key: [u8; 32] = public_key.try_into().map_err(|_| "ED25519_BAD_PUBLIC_KEY")?;
+            let verifying_key = VerifyingKey::from_bytes(&public_key).map_err(|_| "ED25519_BAD_PUBLIC_KEY")?;
+            let signature = Signature::from_slice(signature).map_err(|_| "ED25519_BAD_SIGNATURE")?;
+            verifying_key
+                .verify_strict(message, &signature)
+                .map_err(|_| "ED25519_SIGNATURE_MISMATCH")
+        }
+        SigAlg::ES256 => {
+            use p256::ecdsa::signature::Verifier;
+            let verifying_key = p256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|_| "ES256_BAD_PUBLIC_KEY")?;
+            let signature = p256::ecdsa::Signature::from_slice(signature).map_err(|_| "ES256_BAD_SIGNATURE")?;
+            verifying_key
+                .verify(message, &signature)
+                .map_err(|_| "ES256_SIGNATURE_MISMATCH")
+        }
+        SigAlg::ES256K => {
+            use k256::ecdsa::signature::Verifier;
+            let verifying_key = k256::ecdsa::VerifyingKey::from_sec1_bytes(public_key)
+                .map_err(|_| "ES256K_BAD_PUBLIC_KEY")?;
+            let signature = k256::ecdsa::Signature::from_slice(signature).map_err(|_| "ES256K_BAD_SIGNATURE")?;
+            verifying_key
+                .verify(message, &signature)
+                .map_err(|_| "ES256K_SIGNATURE_MISMATCH")
+        }
+    }
 }
 
 pub struct MyJamAuthorizer;
@@ -131,72 +176,67 @@ impl Authorizer for MyJamAuthorizer {
         let creds: AuthCredentials = match AuthCredentials::decode(&mut param.0.as_slice()) {
             Ok(creds) => creds,
             Err(_) => {
-                return AuthOutput(Sha256::digest(b"DECODE_ERROR").to_vec());
+                return AuthOutput(AuthProblem::DecodeError.encode());
             }
         };
 
-        // --- NONCE VERIFICATION ---
+        // --- NONCE VERIFICATION (must be a currently-valid, unused nonce from `new_nonce`) ---
         let mut state = AUTH_STATE.lock().unwrap();
-        let public_key_hex = hex::encode(creds.public_key);
-        let expected_nonce = state.nonces.get(&creds.public_key).cloned().unwrap_or(0);
+        let public_key_hex = hex::encode(&creds.public_key);
+        prune_expired_nonces(&mut state);
 
-        if creds.nonce != expected_nonce {
+        let nonce_hex = hex::encode(&creds.nonce);
+        if !state.issued_nonces.contains_key(&nonce_hex) {
             info!(
-                target= "authorizer",
-                "Auth failed: Invalid nonce for {}. Expected {}, got {}.",
-                public_key_hex,
-                expected_nonce,
-                creds.nonce
+                target = "authorizer",
+                "Auth failed: nonce {} for {} is unknown, expired, or already used.",
+                nonce_hex,
+                public_key_hex
             );
-            return AuthOutput(Sha256::digest(b"INVALID_NONCE").to_vec());
+            return AuthOutput(AuthProblem::InvalidNonce { nonce: creds.nonce.clone() }.encode());
         }
 
-        // Save authorization record - check if items exist first
-        let payload_value = if let Some(first_item) = package.items.get(0) {
-            serde_json::from_slice(&first_item.payload)
-                .unwrap_or_else(|_| serde_json::json!({ "error": "invalid_payload" }))
-        } else {
-            serde_json::json!({ "error": "no_items" })
+        // --- PAYLOAD & SIGNATURE CHECK ---
+        // The signature must be verified, and must pass, before we touch any
+        // persisted state — a forged credential must never consume a nonce
+        // or write an `AuthRecord`.
+        let Some(first_item) = package.items.get(0) else {
+            return AuthOutput(AuthProblem::NoPayload.encode());
         };
+        let payload_hash: [u8; 32] = Sha256::digest(first_item.payload.as_slice()).into();
+        let signing_message = creds.signing_message(&payload_hash);
+
+        if let Err(reason) = verify_signature(creds.alg, &creds.public_key, &creds.signature, &signing_message) {
+            info!(target = "authorizer", "Auth failed: {}.", reason);
+            let problem = if reason.contains("PUBLIC_KEY") {
+                AuthProblem::InvalidPubKey
+            } else {
+                AuthProblem::SignatureInvalid
+            };
+            return AuthOutput(problem.encode());
+        }
+
+        // --- Signature verified: consume the nonce so it can never be
+        // redeemed again, then persist. ---
+        let payload_value = serde_json::from_slice(&first_item.payload)
+            .unwrap_or_else(|_| serde_json::json!({ "error": "invalid_payload" }));
+
+        state.issued_nonces.remove(&nonce_hex);
 
-        // Update nonce for next time
-        state.nonces.insert(creds.public_key, creds.nonce + 1);
-        
-        // Also update authorizations map with string key for JSON serialization
         state.authorizations.insert(public_key_hex.clone(), AuthRecord {
             public_key: public_key_hex.clone(),
-            nonce: creds.nonce + 1,
+            thumbprint: creds.thumbprint(),
+            nonce: nonce_hex.clone(),
             last_updated: chrono::Utc::now().to_rfc3339(),
             payload: payload_value,
         });
 
-
-        // Save the updated state
         if let Err(e) = save_auth_state(&state) {
             eprintln!("Failed to save auth state: {}", e);
-            return AuthOutput(Sha256::digest(b"STATE_SAVE_ERROR").to_vec());
+            return AuthOutput(AuthProblem::StateSaveError.encode());
         }
 
-        // --- PAYLOAD & SIGNATURE CHECK ---
-        let Some(first_item) = package.items.get(0) else {
-            return AuthOutput(Sha256::digest(b"NO_PAYLOAD").to_vec());
-        };
-        let payload_hash = Sha256::digest(first_item.payload.as_slice());
-
-        let public_key = match VerifyingKey::from_bytes(&creds.public_key) {
-            Ok(pk) => pk,
-            Err(_) => {
-                return AuthOutput(Sha256::digest(b"INVALID_PUBKEY").to_vec());
-            }
-        };
-
-        let signature = Signature::from_bytes(&creds.signature);
-
-        if public_key.verify_strict(&payload_hash, &signature).is_ok() {
-            info!(target = "authorizer", "Authorization successful.");
-            AuthOutput(param.0) // success
-        } else {
-            AuthOutput(Sha256::digest(b"SIGNATURE_INVALID").to_vec())
-        }
+        info!(target = "authorizer", "Authorization successful.");
+        AuthOutput(param.0) // success
     }
 }
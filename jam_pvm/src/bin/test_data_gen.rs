@@ -13,7 +13,8 @@
 // Import necessary crates and types from your project and external libraries.
 use ed25519_dalek::{ Signer, SigningKey };
 use hex;
-use jam_pvm::types::{ AuthCredentials, ServiceCommand }; // Your project's types
+use jam_pvm::authorizer;
+use jam_pvm::types::{ AuthCredentials, ServiceCommand, SigAlg, SignedServiceCommand }; // Your project's types
 use jam_types::{
     AccumulateItem,
     AuthOutput,
@@ -56,18 +57,31 @@ fn main() {
     let public_key_bytes: [u8; 32] = signing_key.verifying_key().to_bytes();
 
     // Define the payload that will be part of the WorkPackage.
-    // The authorizer will hash this payload and verify the signature against it.
+    // The authorizer hashes this payload and verifies the signature against
+    // the canonical `signing_message`: SHA-256(domain tag || alg tag ||
+    // key_authorization), where key_authorization is
+    // `payload_hash "." thumbprint "." nonce`, each hex-encoded.
     let payload_to_sign = b"my test payload for authorization".to_vec();
-    let payload_hash = Sha256::digest(&payload_to_sign);
-
-    // Sign the hash of the payload.
-    let signature = signing_key.sign(&payload_hash);
+    let payload_hash: [u8; 32] = Sha256::digest(&payload_to_sign).into();
+
+    // The nonce must be one actually issued by the authorizer's anti-replay
+    // pool (`authorizer::new_nonce`), hex-decoded back into the raw bytes
+    // `AuthCredentials::nonce` expects.
+    let nonce_hex = authorizer::new_nonce();
+    let nonce = hex::decode(&nonce_hex).expect("new_nonce returns valid hex");
+
+    let unsigned_creds = AuthCredentials {
+        alg: SigAlg::Ed25519,
+        public_key: public_key_bytes.to_vec(),
+        signature: vec![0u8; 64],
+        nonce,
+    };
+    let signing_message = unsigned_creds.signing_message(&payload_hash);
+    let signature = signing_key.sign(&signing_message);
 
-    // Create the authorization credentials. We start with nonce = 0 for the first request.
     let creds = AuthCredentials {
-        public_key: public_key_bytes,
-        signature: signature.to_bytes(),
-        nonce: 0,
+        signature: signature.to_bytes().to_vec(),
+        ..unsigned_creds
     };
 
     // The fields for WorkItem have changed based on the compiler errors.
@@ -180,15 +194,37 @@ fn main() {
 
     // --- 4. Data for /service/on_transfer ---
     println!("## Endpoint: /service/on_transfer");
-    // Create a command to increment the counter.
-    let command = ServiceCommand::IncrementCounter { by: 5 };
+    // Create a command to increment the counter, signed by the same key it
+    // names as `caller` — `on_transfer` now rejects a command whose `caller`
+    // isn't backed by a verified signature from that exact key. `caller`
+    // holds the signer's key_id (SHA-256 of alg tag and public key), which
+    // `creds` shares with the envelope below: same alg, same public key.
+    let command = ServiceCommand::IncrementCounter { caller: creds.key_id(), by: 5 };
+    let envelope = {
+        let alg = SigAlg::Ed25519;
+        let signing_message = {
+            use sha2::Digest as _;
+            let mut hasher = Sha256::new();
+            hasher.update(jam_pvm::types::SERVICE_COMMAND_DOMAIN_TAG);
+            hasher.update([alg.tag()]);
+            hasher.update(command.encode());
+            hasher.finalize().to_vec()
+        };
+        let signature = signing_key.sign(&signing_message);
+        SignedServiceCommand {
+            alg,
+            public_key: public_key_bytes.to_vec(),
+            signature: signature.to_bytes().to_vec(),
+            command,
+        }
+    };
 
     // The Memo field expects a fixed-size array [u8; 128].
-    // We encode the command, then copy it into the array.
-    let encoded_command = command.encode();
+    // We encode the envelope, then copy it into the array.
+    let encoded_envelope = envelope.encode();
     let mut memo_array = [0u8; 128];
-    let len = encoded_command.len().min(128);
-    memo_array[..len].copy_from_slice(&encoded_command[..len]);
+    let len = encoded_envelope.len().min(128);
+    memo_array[..len].copy_from_slice(&encoded_envelope[..len]);
     let memo = Memo(memo_array);
 
     // TransferRecord requires a gas_limit field.
@@ -0,0 +1,111 @@
+//! Local, filesystem-permissioned transport for the PVM's HTTP `Router`:
+//! a Unix domain socket on Linux/macOS, a named pipe on Windows. Selected by
+//! the `JAM_IPC_PATH` env var in `main()`; orchestration tools that would
+//! rather not expose a TCP port reach the exact same handlers over this.
+
+use axum::Router;
+
+/// Serve `app` over the platform's local IPC transport at `path`, forever
+/// (or until the process exits). `main()` only calls this when
+/// `JAM_IPC_PATH` is set, so there's no "disabled" case to represent here.
+pub async fn serve(app: Router, path: String) {
+    #[cfg(unix)]
+    {
+        serve_unix(app, path).await;
+    }
+    #[cfg(windows)]
+    {
+        serve_windows(app, path).await;
+    }
+}
+
+#[cfg(unix)]
+async fn serve_unix(app: Router, path: String) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use tower::Service as _;
+
+    // A stale socket file from a prior, uncleanly-terminated run would
+    // otherwise make `bind` fail with `AddrInUse`.
+    let _ = std::fs::remove_file(&path);
+
+    let listener = match tokio::net::UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("failed to bind IPC socket at {}: {}", path, e);
+            return;
+        }
+    };
+    tracing::info!("listening on unix socket {}", path);
+
+    loop {
+        let (stream, _addr) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                tracing::error!("IPC accept failed: {}", e);
+                continue;
+            }
+        };
+        let tower_service = app.clone();
+        tokio::spawn(async move {
+            let io = TokioIo::new(stream);
+            let hyper_service = hyper::service::service_fn(move |request| {
+                tower_service.clone().call(request)
+            });
+            if
+                let Err(e) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(io, hyper_service).await
+            {
+                tracing::error!("IPC connection error: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(windows)]
+async fn serve_windows(app: Router, path: String) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use tokio::net::windows::named_pipe::ServerOptions;
+    use tower::Service as _;
+
+    tracing::info!("listening on named pipe {}", path);
+
+    // The first pipe instance must be created before any client can
+    // connect; subsequent instances are created in the loop below so there
+    // is always one pending instance waiting for the next connection.
+    let mut next_pipe = match ServerOptions::new().first_pipe_instance(true).create(&path) {
+        Ok(pipe) => pipe,
+        Err(e) => {
+            tracing::error!("failed to create named pipe at {}: {}", path, e);
+            return;
+        }
+    };
+
+    loop {
+        if let Err(e) = next_pipe.connect().await {
+            tracing::error!("named pipe connect failed: {}", e);
+            continue;
+        }
+        let connected = next_pipe;
+        next_pipe = match ServerOptions::new().create(&path) {
+            Ok(pipe) => pipe,
+            Err(e) => {
+                tracing::error!("failed to create next named pipe instance: {}", e);
+                return;
+            }
+        };
+
+        let tower_service = app.clone();
+        tokio::spawn(async move {
+            let io = TokioIo::new(connected);
+            let hyper_service = hyper::service::service_fn(move |request| {
+                tower_service.clone().call(request)
+            });
+            if
+                let Err(e) = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+                    .serve_connection_with_upgrades(io, hyper_service).await
+            {
+                tracing::error!("IPC connection error: {}", e);
+            }
+        });
+    }
+}
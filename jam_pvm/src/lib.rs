@@ -0,0 +1,6 @@
+// src/lib.rs
+
+pub mod authorizer;
+pub mod service;
+pub mod state_store;
+pub mod types;
@@ -1,6 +1,6 @@
 #![cfg(feature = "std")]
 
-use axum::{routing::post, Json, Router};
+use axum::{routing::{get, post}, Json, Router};
 use hex::FromHex;
 use parity_scale_codec::{Decode, Encode};
 use jam_types::{
@@ -10,32 +10,144 @@ use jam_types::{
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use tracing_subscriber::{fmt, EnvFilter};
-use std::sync::Mutex; 
+use std::sync::Mutex;
 use jam_pvm::authorizer::MyJamAuthorizer;
-use jam_pvm::service::MyJamService;
+use jam_pvm::service::{persist_state, MyJamService, GLOBAL_STATE};
+use jam_pvm::types::{AuthProblem, ServiceState};
 use jam_pvm_common::Authorizer as _; // trait for is_authorized
 use jam_pvm_common::Service as _; // trait for service fns
-use once_cell::sync::Lazy; 
+use once_cell::sync::Lazy;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+
+mod ipc;
 
 static SERVICE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
 
+/// Default bound on [`RefineCache`]'s entry count; override with the
+/// `REFINE_CACHE_CAPACITY` env var. `refine` is pure with respect to its
+/// (service_id, payload, package_hash, context, auth_code_hash) inputs, so
+/// memoizing it is safe and cuts latency under repeated queries.
+const DEFAULT_REFINE_CACHE_CAPACITY: usize = 256;
+
+/// Bounded least-recently-used cache of `service_refine` results, keyed by a
+/// SHA-256 hash of the request's (still-hex-encoded) inputs. `order` tracks
+/// recency with the least-recently-used key at the front, mirroring the
+/// eviction style `authorizer::new_nonce` already uses for its issued-nonce
+/// pool (evict one entry, oldest/least-recent first, once at capacity).
+struct RefineCache {
+    capacity: usize,
+    entries: HashMap<[u8; 32], String>,
+    order: VecDeque<[u8; 32]>,
+    hits: u64,
+    misses: u64,
+}
+
+impl RefineCache {
+    fn new(capacity: usize) -> Self {
+        RefineCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    fn touch(&mut self, key: &[u8; 32]) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            self.order.remove(pos);
+        }
+        self.order.push_back(*key);
+    }
+
+    fn get(&mut self, key: &[u8; 32]) -> Option<String> {
+        if let Some(value) = self.entries.get(key).cloned() {
+            self.touch(key);
+            self.hits += 1;
+            Some(value)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    fn insert(&mut self, key: [u8; 32], value: String) {
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            if let Some(lru_key) = self.order.pop_front() {
+                self.entries.remove(&lru_key);
+            }
+        }
+        self.entries.insert(key, value);
+        self.touch(&key);
+    }
+}
+
+static REFINE_CACHE: Lazy<Mutex<RefineCache>> = Lazy::new(|| {
+    let capacity = std::env::var("REFINE_CACHE_CAPACITY")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(DEFAULT_REFINE_CACHE_CAPACITY);
+    Mutex::new(RefineCache::new(capacity))
+});
+
+/// Hash `input`'s hex-encoded fields directly rather than re-encoding the
+/// decoded types: the hex strings already uniquely determine the decoded
+/// refine inputs, so there's nothing a round trip through `decode_scale`
+/// would add to the key.
+fn refine_cache_key(input: &RefineInput) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(input.service_id_hex.as_bytes());
+    hasher.update(input.payload_hex.as_bytes());
+    hasher.update(input.package_hash_hex.as_bytes());
+    hasher.update(input.context_hex.as_bytes());
+    hasher.update(input.auth_code_hash_hex.as_bytes());
+    hasher.finalize().into()
+}
+
+/// Build the service's `Router` once, so it can be served identically over
+/// both the TCP listener and the optional IPC listener below.
+fn build_router() -> Router {
+    Router::new()
+        .route("/authorizer/is_authorized", post(authorizer_is_authorized))
+        .route("/service/refine", post(service_refine))
+        .route("/service/accumulate", post(service_accumulate))
+        .route("/service/accumulate_json", post(service_accumulate_json))
+        .route("/service/on_transfer", post(service_on_transfer))
+        .route("/state/snapshot", get(state_snapshot))
+        .route("/state/restore", post(state_restore))
+        .route("/rpc", post(rpc_handler))
+}
+
 #[tokio::main]
 async fn main() {
     let _ = fmt()
         .with_env_filter(EnvFilter::from_default_env())
         .try_init();
 
-    let app = Router::new()
-        .route("/authorizer/is_authorized", post(authorizer_is_authorized))
-        .route("/service/refine", post(service_refine))
-        .route("/service/accumulate", post(service_accumulate))
-        .route("/service/accumulate_json", post(service_accumulate_json))
-        .route("/service/on_transfer", post(service_on_transfer));
+    let app = build_router();
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 8080));
     tracing::info!("listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.expect("bind failed");
-    axum::serve(listener, app).await.expect("server failed");
+    let tcp_app = app.clone();
+    let tcp_server = tokio::spawn(async move {
+        axum::serve(listener, tcp_app).await.expect("TCP server failed");
+    });
+
+    // `JAM_IPC_PATH` opts into a second, filesystem-permissioned listener
+    // (a Unix domain socket on Linux/macOS, a named pipe on Windows)
+    // serving the identical router, for local orchestrators that would
+    // rather not expose a TCP port at all.
+    match std::env::var("JAM_IPC_PATH") {
+        Ok(path) => {
+            let ipc_server = tokio::spawn(ipc::serve(app, path));
+            let _ = tokio::join!(tcp_server, ipc_server);
+        }
+        Err(_) => {
+            tcp_server.await.expect("TCP server task panicked");
+        }
+    }
 }
 
 // ---- JSON-friendly accumulate endpoint ----
@@ -189,6 +301,50 @@ fn encode_scale<T: Encode>(value: &T) -> String {
 // ---- Handlers ----
 
 async fn authorizer_is_authorized(Json(input): Json<HexInput>) -> Result<Json<HexOutput>, String> {
+    let output = authorizer_is_authorized_core(input)?;
+
+    // `is_authorized` already rejects a replayed/unknown/expired nonce by
+    // encoding an `AuthProblem::InvalidNonce` into the output bytes (see
+    // `authorizer::new_nonce`'s single-use pool, consumed and persisted
+    // under one critical section in the same call that checks it). Surface
+    // that specific rejection as a request error here too, so a direct
+    // REST caller doesn't have to SCALE-decode `output_hex` itself just to
+    // notice its credential was replayed.
+    if let Ok(bytes) = hex_to_vec(&output.output_hex) {
+        if let Ok(AuthProblem::InvalidNonce { nonce }) = jam_codec::Decode::decode(&mut bytes.as_slice()) {
+            return Err(format!(
+                "credential rejected: nonce {} was already used, is expired, or is unknown (possible replay)",
+                hex::encode(nonce)
+            ));
+        }
+    }
+
+    Ok(Json(output))
+}
+
+async fn service_refine(Json(input): Json<RefineInput>) -> Result<Json<RefineOutput>, String> {
+    service_refine_core(input).map(Json)
+}
+
+async fn service_accumulate(
+    Json(input): Json<AccumulateInput>,
+) -> Result<Json<AccumulateOutput>, String> {
+    let _guard = SERVICE_LOCK.lock().unwrap();
+    service_accumulate_core(input).map(Json)
+}
+
+async fn service_on_transfer(Json(input): Json<OnTransferInput>) -> Result<Json<serde_json::Value>, String> {
+    let _guard = SERVICE_LOCK.lock().unwrap();
+    service_on_transfer_core(input)?;
+    Ok(Json(serde_json::json!({"status":"ok"})))
+}
+
+// ---- Handler bodies, factored out so the /rpc dispatch can call them
+// without each one re-acquiring SERVICE_LOCK (the state-mutating REST
+// handlers above, accumulate and on_transfer, lock around a single call;
+// /rpc locks once for a whole request/batch instead). ----
+
+fn authorizer_is_authorized_core(input: HexInput) -> Result<HexOutput, String> {
     let param_bytes = hex_to_vec(&input.param_hex)?;
     let param = AuthParam(param_bytes);
     let package: WorkPackage = decode_scale(&input.package_hex)?;
@@ -200,16 +356,34 @@ async fn authorizer_is_authorized(Json(input): Json<HexInput>) -> Result<Json<He
         core_index,
     );
 
-    // --- THIS IS THE FIX ---
-    // The `out` variable already contains the raw bytes we need (`out.0`).
-    // We should hex-encode these bytes directly, NOT SCALE-encode the `AuthOutput` struct again.
-    Ok(Json(HexOutput {
+    // `out` already holds the raw bytes we need (`out.0`); hex-encode them
+    // directly rather than SCALE-encoding the `AuthOutput` wrapper again.
+    Ok(HexOutput {
         output_hex: hex::encode(&out.0),
-    }))
-    // --- END OF FIX ---
+    })
 }
 
-async fn service_refine(Json(input): Json<RefineInput>) -> Result<Json<RefineOutput>, String> {
+fn service_refine_core(input: RefineInput) -> Result<RefineOutput, String> {
+    let key = refine_cache_key(&input);
+    {
+        let mut cache = REFINE_CACHE.lock().unwrap();
+        if let Some(work_output_hex) = cache.get(&key) {
+            tracing::debug!(
+                target = "service::refine",
+                "refine cache hit (hits={}, misses={})",
+                cache.hits,
+                cache.misses
+            );
+            return Ok(RefineOutput { work_output_hex });
+        }
+        tracing::debug!(
+            target = "service::refine",
+            "refine cache miss (hits={}, misses={})",
+            cache.hits,
+            cache.misses
+        );
+    }
+
     let id: ServiceId = decode_scale(&input.service_id_hex)?;
     let payload: WorkPayload = decode_scale(&input.payload_hex)?;
     let package_hash: WorkPackageHash = decode_scale(&input.package_hash_hex)?;
@@ -223,38 +397,322 @@ async fn service_refine(Json(input): Json<RefineInput>) -> Result<Json<RefineOut
         context,
         auth_code_hash,
     );
-    // Ok(Json(RefineOutput {
-    //     work_output_hex: encode_scale(&out),
-    // }))
-    Ok(Json(RefineOutput {
-        work_output_hex: hex::encode(&out.0),
-    }))
+    let work_output_hex = hex::encode(&out.0);
+
+    REFINE_CACHE.lock().unwrap().insert(key, work_output_hex.clone());
+
+    Ok(RefineOutput { work_output_hex })
 }
 
-async fn service_accumulate(
-    Json(input): Json<AccumulateInput>,
-) -> Result<Json<AccumulateOutput>, String> {
+fn service_accumulate_core(input: AccumulateInput) -> Result<AccumulateOutput, String> {
     let slot: Slot = decode_scale(&input.slot_hex)?;
     let id: ServiceId = decode_scale(&input.service_id_hex)?;
     let items: Vec<AccumulateItem> = decode_scale(&input.items_hex)?;
 
-    // --- FIX: Acquire lock before accessing state ---
-    let _guard = SERVICE_LOCK.lock().unwrap();
     let out: Option<Hash> = <MyJamService as jam_pvm_common::Service>::accumulate(slot, id, items);
-    
-    Ok(Json(AccumulateOutput {
+
+    Ok(AccumulateOutput {
         hash_hex: out.map(|h| encode_scale(&h)),
-    }))
+    })
 }
 
-async fn service_on_transfer(Json(input): Json<OnTransferInput>) -> Result<Json<serde_json::Value>, String> {
+fn service_on_transfer_core(input: OnTransferInput) -> Result<(), String> {
     let slot: Slot = decode_scale(&input.slot_hex)?;
     let id: ServiceId = decode_scale(&input.service_id_hex)?;
     let transfers: Vec<TransferRecord> = decode_scale(&input.transfers_hex)?;
 
-    // --- FIX: Acquire lock before accessing state ---
-    let _guard = SERVICE_LOCK.lock().unwrap();
     <MyJamService as jam_pvm_common::Service>::on_transfer(slot, id, transfers);
+    Ok(())
+}
 
-    Ok(Json(serde_json::json!({"status":"ok"})))
+// ---- State snapshot export/import ----
+//
+// `GLOBAL_STATE` otherwise only changes via `accumulate`/`on_transfer`; these
+// two endpoints let an operator checkpoint it to a portable blob and later
+// restore it (e.g. into a freshly started process), verifying integrity
+// before ever applying an offered snapshot.
+
+/// Bumped whenever `ServiceState`'s SCALE encoding changes shape, so an
+/// older snapshot is rejected instead of silently misdecoded.
+const STATE_SCHEMA_VERSION: u32 = 1;
+
+/// How long a [`submission_key`] stays blacklisted in [`FAILED_MANIFESTS`],
+/// mirroring `authorizer.rs`'s `NONCE_TTL_SECS`: past this, a resubmission
+/// of the same bytes is re-parsed from scratch rather than assumed to still
+/// be bad.
+const FAILED_MANIFEST_TTL_SECS: i64 = 3600;
+
+/// Hard cap on tracked failed submissions, so an unauthenticated caller
+/// repeatedly POSTing distinct garbage to `/state/restore` can't grow
+/// `FAILED_MANIFESTS` without bound. Mirrors `authorizer.rs`'s
+/// `MAX_ISSUED_NONCES`; the oldest entry is evicted once the cap is hit.
+const MAX_FAILED_MANIFESTS: usize = 10_000;
+
+/// Keys ([`submission_key`]) of whole `/state/restore` submissions that
+/// have already failed to import (bad schema version, digest mismatch, or
+/// undecodable state), so a repeatedly-offered corrupt snapshot is refused
+/// without re-hashing or re-parsing it. Keyed on the full submission rather
+/// than the caller-claimed `digest_hex` alone — see `submission_key`'s doc
+/// comment for why that distinction matters. Value is the unix timestamp
+/// (seconds) the entry was recorded at, so it can be pruned like
+/// `authorizer.rs`'s `issued_nonces`.
+static FAILED_MANIFESTS: Lazy<Mutex<HashMap<String, i64>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Drop any blacklist entry older than [`FAILED_MANIFEST_TTL_SECS`], then,
+/// if still at capacity, evict the single oldest entry. Called before every
+/// read or write of [`FAILED_MANIFESTS`] so the map never grows past
+/// [`MAX_FAILED_MANIFESTS`].
+fn prune_failed_manifests(manifests: &mut HashMap<String, i64>) {
+    let now = chrono::Utc::now().timestamp();
+    manifests.retain(|_, recorded_at| now - *recorded_at < FAILED_MANIFEST_TTL_SECS);
+
+    if manifests.len() >= MAX_FAILED_MANIFESTS {
+        if let Some(oldest) = manifests
+            .iter()
+            .min_by_key(|(_, recorded_at)| **recorded_at)
+            .map(|(key, _)| key.clone())
+        {
+            manifests.remove(&oldest);
+        }
+    }
+}
+
+/// Record `submission_key` as a failed submission, pruning first so the
+/// blacklist stays bounded.
+fn blacklist_failed_manifest(submission_key: &str) {
+    let mut manifests = FAILED_MANIFESTS.lock().unwrap();
+    prune_failed_manifests(&mut manifests);
+    manifests.insert(submission_key.to_string(), chrono::Utc::now().timestamp());
+}
+
+#[derive(Serialize, Deserialize)]
+struct SnapshotManifest {
+    schema_version: u32,
+    /// Hex-encoded SHA-256 digest of `state_hex`'s decoded bytes.
+    digest_hex: String,
+}
+
+#[derive(Serialize)]
+struct SnapshotResponse {
+    manifest: SnapshotManifest,
+    state_hex: String, // ServiceState (SCALE hex)
+}
+
+#[derive(Deserialize)]
+struct RestoreRequest {
+    manifest: SnapshotManifest,
+    state_hex: String, // ServiceState (SCALE hex)
+}
+
+fn state_digest_hex(state_bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(state_bytes))
+}
+
+async fn state_snapshot() -> Result<Json<SnapshotResponse>, String> {
+    let state_bytes = {
+        let state = GLOBAL_STATE.lock().unwrap();
+        jam_codec::Encode::encode(&*state)
+    };
+    let digest_hex = state_digest_hex(&state_bytes);
+
+    Ok(Json(SnapshotResponse {
+        manifest: SnapshotManifest { schema_version: STATE_SCHEMA_VERSION, digest_hex },
+        state_hex: hex::encode(&state_bytes),
+    }))
+}
+
+/// A blacklist key derived from the whole offered submission (claimed
+/// digest *and* claimed state bytes), not from `digest_hex` alone.
+/// `digest_hex` is attacker-controlled: keying the blacklist on it directly
+/// would let a submission with a legitimate `digest_hex` but garbage
+/// `state_hex` permanently poison every future (correct) restore attempt
+/// for that same legitimate digest.
+fn submission_key(digest_hex: &str, state_hex: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(digest_hex.as_bytes());
+    hasher.update(state_hex.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+async fn state_restore(Json(input): Json<RestoreRequest>) -> Result<Json<serde_json::Value>, String> {
+    let digest_hex = input.manifest.digest_hex.clone();
+    let submission_key = submission_key(&digest_hex, &input.state_hex);
+
+    {
+        let mut manifests = FAILED_MANIFESTS.lock().unwrap();
+        prune_failed_manifests(&mut manifests);
+        if manifests.contains_key(&submission_key) {
+            return Err(format!("this exact submission previously failed to import (claimed digest {}), refusing without re-parsing", digest_hex));
+        }
+    }
+
+    if input.manifest.schema_version != STATE_SCHEMA_VERSION {
+        blacklist_failed_manifest(&submission_key);
+        return Err(format!(
+            "unsupported snapshot schema_version {} (expected {})",
+            input.manifest.schema_version,
+            STATE_SCHEMA_VERSION
+        ));
+    }
+
+    let state_bytes = hex_to_vec(&input.state_hex)?;
+    let actual_digest_hex = state_digest_hex(&state_bytes);
+    if actual_digest_hex != digest_hex {
+        blacklist_failed_manifest(&submission_key);
+        return Err(format!(
+            "digest mismatch: manifest claims {}, blob hashes to {}",
+            digest_hex,
+            actual_digest_hex
+        ));
+    }
+
+    let new_state: ServiceState = jam_codec::Decode::decode(&mut state_bytes.as_slice())
+        .map_err(|_| {
+            blacklist_failed_manifest(&submission_key);
+            "failed to decode ServiceState from snapshot".to_string()
+        })?;
+
+    // Hold SERVICE_LOCK for the whole restore so an accumulate/on_transfer
+    // in another request can't interleave mid-import and observe (or
+    // clobber) a half-applied state.
+    let _guard = SERVICE_LOCK.lock().unwrap();
+    *GLOBAL_STATE.lock().unwrap() = new_state.clone();
+    persist_state(&new_state);
+
+    Ok(Json(serde_json::json!({"status": "ok"})))
+}
+
+// ---- JSON-RPC 2.0 transport ----
+//
+// A single `/rpc` endpoint that dispatches on `method` instead of one
+// bespoke route per operation, so a caller can pipeline several calls
+// (e.g. refine, then accumulate, then on_transfer) in one request. Accepts
+// either a lone request object or a batch array, per the JSON-RPC 2.0 spec.
+
+#[derive(Deserialize)]
+struct JsonRpcRequest {
+    #[serde(default)]
+    jsonrpc: Option<String>,
+    method: String,
+    #[serde(default)]
+    params: Option<serde_json::Value>,
+    /// Absent entirely => a notification: dispatched for its side effects,
+    /// but no response is emitted for it. An explicit `"id": null` is kept
+    /// as `Some(Value::Null)` and is answered.
+    #[serde(default, deserialize_with = "deserialize_present_id")]
+    id: Option<serde_json::Value>,
+}
+
+fn deserialize_present_id<'de, D: serde::Deserializer<'de>>(d: D) -> Result<Option<serde_json::Value>, D::Error> {
+    <serde_json::Value as serde::Deserialize>::deserialize(d).map(Some)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonRpcBatch {
+    Single(JsonRpcRequest),
+    Batch(Vec<JsonRpcRequest>),
+}
+
+#[derive(Serialize)]
+struct JsonRpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Serialize)]
+struct JsonRpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcErrorObject>,
+    id: serde_json::Value,
+}
+
+impl JsonRpcResponse {
+    fn ok(id: serde_json::Value, result: serde_json::Value) -> Self {
+        Self { jsonrpc: "2.0", result: Some(result), error: None, id }
+    }
+
+    fn err(id: serde_json::Value, code: i64, message: impl Into<String>) -> Self {
+        Self { jsonrpc: "2.0", result: None, error: Some(JsonRpcErrorObject { code, message: message.into() }), id }
+    }
+}
+
+const JSONRPC_INVALID_REQUEST: i64 = -32600;
+const JSONRPC_METHOD_NOT_FOUND: i64 = -32601;
+const JSONRPC_INVALID_PARAMS: i64 = -32602;
+const JSONRPC_INTERNAL_ERROR: i64 = -32603;
+
+/// Dispatch `method` against `params`, assuming `SERVICE_LOCK` (if needed)
+/// is already held by the caller.
+fn dispatch_method(method: &str, params: Option<serde_json::Value>) -> Result<serde_json::Value, (i64, String)> {
+    let params = params.unwrap_or(serde_json::Value::Null);
+    let invalid_params = |e: serde_json::Error| (JSONRPC_INVALID_PARAMS, format!("invalid params: {}", e));
+    let internal = |e: String| (JSONRPC_INTERNAL_ERROR, e);
+
+    match method {
+        "authorizer_isAuthorized" => {
+            let input: HexInput = serde_json::from_value(params).map_err(invalid_params)?;
+            let out = authorizer_is_authorized_core(input).map_err(internal)?;
+            Ok(serde_json::to_value(out).expect("HexOutput always serializes"))
+        }
+        "service_refine" => {
+            let input: RefineInput = serde_json::from_value(params).map_err(invalid_params)?;
+            let out = service_refine_core(input).map_err(internal)?;
+            Ok(serde_json::to_value(out).expect("RefineOutput always serializes"))
+        }
+        "service_accumulate" => {
+            let input: AccumulateInput = serde_json::from_value(params).map_err(invalid_params)?;
+            let out = service_accumulate_core(input).map_err(internal)?;
+            Ok(serde_json::to_value(out).expect("AccumulateOutput always serializes"))
+        }
+        "service_onTransfer" => {
+            let input: OnTransferInput = serde_json::from_value(params).map_err(invalid_params)?;
+            service_on_transfer_core(input).map_err(internal)?;
+            Ok(serde_json::json!({"status": "ok"}))
+        }
+        other => Err((JSONRPC_METHOD_NOT_FOUND, format!("method not found: {}", other))),
+    }
+}
+
+/// Run one request (notification or not) and, for anything but a
+/// notification, produce its response.
+fn dispatch_one(req: JsonRpcRequest) -> Option<JsonRpcResponse> {
+    let id = req.id.clone();
+
+    if !matches!(req.jsonrpc.as_deref(), None | Some("2.0")) {
+        return id.map(|id| JsonRpcResponse::err(id, JSONRPC_INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+    }
+
+    let outcome = dispatch_method(&req.method, req.params);
+    let id = id?;
+    Some(match outcome {
+        Ok(result) => JsonRpcResponse::ok(id, result),
+        Err((code, message)) => JsonRpcResponse::err(id, code, message),
+    })
+}
+
+async fn rpc_handler(Json(batch): Json<JsonRpcBatch>) -> Json<serde_json::Value> {
+    // Acquired once for the whole call (single request or batch) rather
+    // than per-method, so a pipelined refine+accumulate+on_transfer batch
+    // runs against one consistent view of state instead of interleaving
+    // with other requests mid-batch.
+    let _guard = SERVICE_LOCK.lock().unwrap();
+
+    match batch {
+        JsonRpcBatch::Single(req) => match dispatch_one(req) {
+            Some(resp) => Json(serde_json::to_value(resp).expect("JsonRpcResponse always serializes")),
+            None => Json(serde_json::Value::Null),
+        },
+        JsonRpcBatch::Batch(reqs) => {
+            let responses: Vec<serde_json::Value> = reqs
+                .into_iter()
+                .filter_map(dispatch_one)
+                .map(|resp| serde_json::to_value(resp).expect("JsonRpcResponse always serializes"))
+                .collect();
+            Json(serde_json::Value::Array(responses))
+        }
+    }
 }
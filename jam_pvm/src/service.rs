@@ -1,7 +1,9 @@
 // src/service.rs
 extern crate alloc;
 
-use crate::types::{ AuthCredentials, ServiceCommand, ServiceState };
+use crate::authorizer::verify_signature;
+use crate::state_store::{self, StateStore};
+use crate::types::{ AuthCredentials, AuthProblem, Role, ServiceCommand, ServiceState, SignedServiceCommand };
 use alloc::vec::Vec;
 use jam_codec::{ Decode, Encode };
 use jam_pvm_common::{ declare_service, info, Service };
@@ -20,9 +22,43 @@ use jam_types::{
 use lazy_static::lazy_static;
 use std::sync::Mutex;
 
-// Global in-memory state instead of set_storage/get_storage
+/// Key this state is stored under within whichever `StateStore` is active.
+const SERVICE_STATE_KEY: &str = "service_state.scale";
+
 lazy_static! {
-    pub static ref GLOBAL_STATE: Mutex<ServiceState> = Mutex::new(ServiceState::default());
+    // `STATE_DIR` (default ".") and `STATE_STORE` (default "file") select
+    // where this lives; see `state_store::from_env`. Sharing the selection
+    // logic with `authorizer.rs` lets both sides of this service agree on
+    // one store, when `STATE_STORE`/`STATE_DIR` point them to the same place
+    // (their default file directories differ), instead of the authorizer
+    // reading a file while this state stays process-local.
+    static ref STATE_STORE: Box<dyn StateStore> = state_store::from_env(".");
+    pub static ref GLOBAL_STATE: Mutex<ServiceState> = Mutex::new(load_service_state());
+}
+
+fn load_service_state() -> ServiceState {
+    match STATE_STORE.load(SERVICE_STATE_KEY) {
+        Ok(Some(bytes)) => ServiceState::decode(&mut bytes.as_slice()).unwrap_or_default(),
+        Ok(None) => ServiceState::default(),
+        Err(e) => {
+            eprintln!("Failed to read service state: {}", e);
+            ServiceState::default()
+        }
+    }
+}
+
+fn save_service_state(state: &ServiceState) {
+    if let Err(e) = STATE_STORE.store(SERVICE_STATE_KEY, &state.encode()) {
+        eprintln!("Failed to save service state: {}", e);
+    }
+}
+
+/// Flush `state` to the active `StateStore`, for callers outside this
+/// module that replace `GLOBAL_STATE` wholesale (e.g. the `/state/restore`
+/// HTTP endpoint importing a snapshot) and need the same on-disk
+/// persistence `accumulate`/`on_transfer` already get after every mutation.
+pub fn persist_state(state: &ServiceState) {
+    save_service_state(state);
 }
 
 pub struct MyJamService;
@@ -58,20 +94,41 @@ impl Service for MyJamService {
                 state.counter += 1;
                 state.last_payload_hash = item.payload.0.clone();
 
-                // Try decode
+                // Try decode. The authorizer already redeemed `creds.nonce`
+                // from its single-use ACME-style pool before this package
+                // could be included, so there's no per-key counter left to
+                // check here — just record who authorized this accumulation.
                 match AuthCredentials::decode(&mut item.auth_output.0.as_slice()) {
                     Ok(creds) => {
-                        let nonce = state.nonces.entry(creds.public_key.clone()).or_insert(0);
-                        *nonce += 1;
-                       println!("✅ Nonce for pk {:?} incremented to {}.", creds.public_key, *nonce);
+                        println!(
+                            "✅ Accumulating item authorized by pk {:?} (nonce {}).",
+                            creds.public_key,
+                            hex::encode(&creds.nonce)
+                        );
                     }
                     Err(e) => {
-                        info!(
-                            target = "service::accumulate",
-                            "⚠️ Failed to decode AuthCredentials from auth_output: {:?}. Raw auth_output = {:?}",
-                            e,
-                            item.auth_output.0
-                        );
+                        // `is_authorized` encodes a typed `AuthProblem` instead
+                        // of `AuthCredentials` on failure; try that decoding
+                        // before falling back to a raw dump, so a rejected
+                        // package still surfaces a machine-readable reason
+                        // here instead of being silently swallowed.
+                        match AuthProblem::decode(&mut item.auth_output.0.as_slice()) {
+                            Ok(problem) => {
+                                info!(
+                                    target = "service::accumulate",
+                                    "⚠️ auth_output was a typed AuthProblem, not AuthCredentials: {:?}.",
+                                    problem
+                                );
+                            }
+                            Err(_) => {
+                                info!(
+                                    target = "service::accumulate",
+                                    "⚠️ Failed to decode AuthCredentials from auth_output: {:?}. Raw auth_output = {:?}",
+                                    e,
+                                    item.auth_output.0
+                                );
+                            }
+                        }
                     }
                 }
             } else {
@@ -96,6 +153,8 @@ impl Service for MyJamService {
             state.counter
         );
 
+        save_service_state(&state);
+
         None
     }
 
@@ -115,32 +174,186 @@ impl Service for MyJamService {
         info!(target = "service::on_transfer", "Read initial state: counter = {}.", state.counter);
 
         for transfer in transfers {
-            if let Ok(command) = ServiceCommand::decode(&mut &transfer.memo.0[..]) {
-                info!(target = "service::on_transfer", "Decoded command: {:?}.", command);
-                match command {
-                    ServiceCommand::IncrementCounter { by } => {
-                        state.counter += by;
+            if let Ok(envelope) = SignedServiceCommand::decode(&mut &transfer.memo.0[..]) {
+                // Every `caller` the command claims must be this envelope's
+                // own signer, and the signature over the whole command must
+                // verify, before any of it is trusted — otherwise `caller`
+                // is just a field an attacker typed in (see
+                // `SignedServiceCommand::callers_match_signer`).
+                if !envelope.callers_match_signer() {
+                    info!(
+                        target = "service::on_transfer",
+                        "Command rejected: caller field does not match the envelope's signer."
+                    );
+                    continue;
+                }
+                if
+                    let Err(reason) = verify_signature(
+                        envelope.alg,
+                        &envelope.public_key,
+                        &envelope.signature,
+                        &envelope.signing_message()
+                    )
+                {
+                    info!(target = "service::on_transfer", "Command rejected: signature {}.", reason);
+                    continue;
+                }
+
+                info!(target = "service::on_transfer", "Decoded command: {:?}.", envelope.command);
+                match envelope.command {
+                    ServiceCommand::Batch { caller, nonce, commands } => {
+                        run_batch(&mut state, caller, nonce, commands);
                     }
-                    ServiceCommand::ResetState => {
-                        if u64::from(transfer.source) == state.admin {
-                            *state = ServiceState::default(); // ✅ reset cleanly
-                            state.admin = u64::from(transfer.source);
-                        } else {
-                            info!(
-                                target = "service::on_transfer",
-                                "ACCESS DENIED: ResetState is admin-only."
-                            );
+                    command => {
+                        if let Err(reason) = apply_command(&mut state, command) {
+                            info!(target = "service::on_transfer", "Command rejected: {}.", reason);
                         }
                     }
                 }
             } else {
                 info!(
                     target = "service::on_transfer",
-                    "Could not decode command from transfer memo."
+                    "Could not decode signed command from transfer memo."
                 );
             }
         }
 
         info!(target = "service::on_transfer", "Updated state: counter = {}.", state.counter);
+
+        save_service_state(&state);
+    }
+}
+
+/// Apply a single, non-`Batch` command to `state`.
+///
+/// Returns `Err` with a human-readable reason on access denial, arithmetic
+/// overflow, a nested `Batch`, or an unsupported command tag. Only the last
+/// changes `state`, by bumping `rejected_commands`; the others leave it
+/// untouched.
+fn apply_command(state: &mut ServiceState, command: ServiceCommand) -> Result<(), &'static str> {
+    match command {
+        ServiceCommand::IncrementCounter { caller, by } => {
+            if !state.authorize(&caller, Role::Operator) {
+                return Err("IncrementCounter requires Operator");
+            }
+            state.counter = state.counter.checked_add(by).ok_or("counter overflow")?;
+            Ok(())
+        }
+        ServiceCommand::ResetState { caller } => {
+            if !state.authorize(&caller, Role::Admin) {
+                return Err("ResetState is admin-only");
+            }
+            let authorities = state.authorities.clone();
+            *state = ServiceState::default(); // ✅ reset cleanly
+            state.authorities = authorities;
+            Ok(())
+        }
+        ServiceCommand::GrantRole { caller, key, role } => {
+            if !state.authorize(&caller, Role::Admin) {
+                return Err("GrantRole is admin-only");
+            }
+            state.authorities.insert(key, role);
+            Ok(())
+        }
+        ServiceCommand::RevokeRole { caller, key } => {
+            if !state.authorize(&caller, Role::Admin) {
+                return Err("RevokeRole is admin-only");
+            }
+            state.authorities.remove(&key);
+            Ok(())
+        }
+        ServiceCommand::Batch { .. } => Err("nested Batch is not allowed"),
+        ServiceCommand::Unsupported { tag, raw: _ } => {
+            state.rejected_commands += 1;
+            info!(
+                target = "service::on_transfer",
+                "Unsupported command tag {} (deployment supports {:?}); rejected_commands now {}.",
+                tag,
+                ServiceCommand::supported_commands(),
+                state.rejected_commands
+            );
+            Err("unsupported command tag")
+        }
+    }
+}
+
+/// Run a `Batch` against `state` with all-or-nothing semantics.
+///
+/// The batch's `nonce` is checked and consumed exactly once, regardless of
+/// how many sub-commands it contains. Sub-command outcomes are logged in
+/// order so callers can see which one would have failed.
+fn run_batch(state: &mut ServiceState, caller: [u8; 32], nonce: u64, commands: Vec<ServiceCommand>) {
+    let window = state.nonces.get(&caller).copied().unwrap_or_default();
+    if !window.would_accept(nonce) {
+        info!(
+            target = "service::on_transfer",
+            "ACCESS DENIED: Batch nonce {} was already seen (window highest = {}).",
+            nonce,
+            window.highest
+        );
+        return;
+    }
+
+    let mut scratch = state.clone();
+    let mut results: Vec<Result<(), &'static str>> = Vec::with_capacity(commands.len());
+    for sub in commands {
+        results.push(apply_command(&mut scratch, sub));
+    }
+
+    let all_ok = results.iter().all(Result::is_ok);
+    if all_ok {
+        let mut window = window;
+        window.accept(nonce);
+        scratch.nonces.insert(caller, window);
+        *state = scratch;
+    }
+    info!(
+        target = "service::on_transfer",
+        "Batch applied = {}, per-command results: {:?}.",
+        all_ok,
+        results
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_batch_applies_all_or_nothing() {
+        let mut state = ServiceState::default();
+        let caller = [9u8; 32];
+        // Operator is enough for IncrementCounter but not for ResetState, so
+        // the second sub-command below is the one expected to fail.
+        state.authorities.insert(caller, Role::Operator);
+
+        let commands = vec![
+            ServiceCommand::IncrementCounter { caller, by: 5 },
+            ServiceCommand::ResetState { caller },
+        ];
+
+        run_batch(&mut state, caller, 1, commands);
+
+        assert_eq!(
+            state.counter, 0,
+            "a failing sub-command must roll back the whole batch, including the earlier successful IncrementCounter"
+        );
+        assert_eq!(
+            state.nonces.get(&caller), None,
+            "the batch nonce must not be consumed when the batch doesn't apply"
+        );
+    }
+
+    #[test]
+    fn run_batch_rejects_a_replayed_nonce() {
+        let mut state = ServiceState::default();
+        let caller = [9u8; 32];
+        state.authorities.insert(caller, Role::Operator);
+
+        run_batch(&mut state, caller, 1, vec![ServiceCommand::IncrementCounter { caller, by: 5 }]);
+        assert_eq!(state.counter, 5);
+
+        run_batch(&mut state, caller, 1, vec![ServiceCommand::IncrementCounter { caller, by: 5 }]);
+        assert_eq!(state.counter, 5, "replaying the same batch nonce must not apply the batch again");
     }
 }
@@ -0,0 +1,159 @@
+// src/state_store.rs
+//! Pluggable persistence for `AuthState`/`ServiceState`.
+//!
+//! The authorizer used to hardwire a relative `STATE_FILE` path and the
+//! service kept a separate in-memory-only `GLOBAL_STATE` that never
+//! touched disk at all, so two PVM instances could never agree on one
+//! view of nonce/authorization state. Both now load from and save to a
+//! [`StateStore`] chosen by the same [`from_env`] selection, so the backing
+//! storage is a deployment choice rather than something baked into the
+//! authorizer's source.
+
+use std::collections::HashMap as StdHashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// A place to persist a service's serialized state, keyed by name (e.g.
+/// `"updated_state.json"`). Kept synchronous rather than `async fn` so it
+/// can be called from the `lazy_static` initializers `authorizer.rs` and
+/// `service.rs` already use; [`S3Store`] bridges to an async SDK itself.
+pub trait StateStore: Send + Sync {
+    fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>>;
+    fn store(&self, key: &str, bytes: &[u8]) -> std::io::Result<()>;
+}
+
+/// The original behavior: one file per key under `dir`.
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+        match std::fs::read(self.path(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        std::fs::write(self.path(key), bytes)
+    }
+}
+
+/// Ephemeral, process-local storage — the pre-refactor behavior of
+/// `service.rs`'s `GLOBAL_STATE`, lifted behind the same trait so tests
+/// and short-lived runs don't need a filesystem at all.
+#[derive(Default)]
+pub struct InMemoryStore {
+    entries: Mutex<StdHashMap<String, Vec<u8>>>,
+}
+
+impl StateStore for InMemoryStore {
+    fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+        Ok(self.entries.lock().unwrap().get(key).cloned())
+    }
+
+    fn store(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        self.entries.lock().unwrap().insert(key.to_string(), bytes.to_vec());
+        Ok(())
+    }
+}
+
+/// Speaks the S3 API (AWS S3, MinIO, Garage, ...) so auth/service state can
+/// live in shared remote storage instead of a relative path on one
+/// instance's disk, letting multiple PVM instances share one consistent
+/// view of nonce/authorization state.
+///
+/// Requires the `aws-sdk-s3`, `aws-config`, and `tokio` crates; add them to
+/// `Cargo.toml` if they aren't already present. `load`/`store` drive the
+/// async SDK on a dedicated runtime owned by this store,
+/// so the synchronous [`StateStore`] trait doesn't need to assume an
+/// ambient `tokio` runtime is already running when it's constructed.
+pub struct S3Store {
+    bucket: String,
+    client: aws_sdk_s3::Client,
+    runtime: tokio::runtime::Runtime,
+}
+
+impl S3Store {
+    /// `endpoint` overrides the default AWS endpoint resolution, e.g. to
+    /// point at a self-hosted Garage/MinIO deployment.
+    pub fn new(bucket: impl Into<String>, endpoint: Option<String>) -> Self {
+        let runtime = tokio::runtime::Runtime::new()
+            .expect("failed to start a runtime for the S3 state store");
+        let client = runtime.block_on(async {
+            let mut loader = aws_config::from_env();
+            if let Some(endpoint) = &endpoint {
+                loader = loader.endpoint_url(endpoint);
+            }
+            aws_sdk_s3::Client::new(&loader.load().await)
+        });
+        Self { bucket: bucket.into(), client, runtime }
+    }
+}
+
+impl StateStore for S3Store {
+    fn load(&self, key: &str) -> std::io::Result<Option<Vec<u8>>> {
+        let result = self.runtime.block_on(
+            self.client.get_object().bucket(&self.bucket).key(key).send(),
+        );
+        match result {
+            Ok(output) => {
+                let bytes = self
+                    .runtime
+                    .block_on(output.body.collect())
+                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))?
+                    .into_bytes()
+                    .to_vec();
+                Ok(Some(bytes))
+            }
+            Err(aws_sdk_s3::error::SdkError::ServiceError(e)) if e.err().is_no_such_key() => Ok(None),
+            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e.to_string())),
+        }
+    }
+
+    fn store(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        self.runtime
+            .block_on(
+                self.client
+                    .put_object()
+                    .bucket(&self.bucket)
+                    .key(key)
+                    .body(bytes.to_vec().into())
+                    .send(),
+            )
+            .map(|_| ())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e.to_string()))
+    }
+}
+
+/// Select a store by the `STATE_STORE` env var: `file` (the default),
+/// `memory`, or `s3`. `default_dir` is the `FileStore` directory used when
+/// `STATE_DIR` isn't set, letting each caller (authorizer vs. service)
+/// keep its own pre-refactor default path.
+pub fn from_env(default_dir: &str) -> Box<dyn StateStore> {
+    match std::env::var("STATE_STORE").as_deref() {
+        Ok("memory") => Box::new(InMemoryStore::default()),
+        Ok("s3") => {
+            let bucket = std::env::var("STATE_S3_BUCKET")
+                .expect("STATE_S3_BUCKET must be set when STATE_STORE=s3");
+            let endpoint = std::env::var("STATE_S3_ENDPOINT").ok();
+            Box::new(S3Store::new(bucket, endpoint))
+        }
+        _ => {
+            let dir = std::env::var("STATE_DIR").unwrap_or_else(|_| default_dir.to_string());
+            Box::new(FileStore::new(dir))
+        }
+    }
+}
@@ -3,14 +3,193 @@
 extern crate alloc;
 
 use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 use jam_codec::{Decode, Encode};
+use sha2::{Digest, Sha256};
 
-/// Credentials for authorizing a WorkPackage, including a nonce.
+/// Fixed prefix mixed into every signed message, so a signature produced for
+/// this service can never be replayed as a valid signature in another
+/// context.
+pub const AUTH_DOMAIN_TAG: &[u8] = b"jam_pvm.auth_credentials.v1";
+
+/// The signature scheme an [`AuthCredentials`] was produced with, mirroring
+/// the JWS "alg" abstraction (ES256/EdDSA/ES256K families) so a client can
+/// pick a curve without the authorizer being locked to one.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SigAlg {
+    /// EdDSA over Curve25519 (ed25519-dalek). 32-byte public key, 64-byte
+    /// signature.
+    Ed25519,
+    /// ECDSA over NIST P-256 with SHA-256 (the JWS "ES256" alg). SEC1
+    /// public key (33 bytes compressed), 64-byte fixed-size (r || s)
+    /// signature.
+    ES256,
+    /// ECDSA over secp256k1 with SHA-256 (the JWS "ES256K" alg). Same
+    /// encoding shape as `ES256`, different curve.
+    ES256K,
+}
+
+impl SigAlg {
+    /// Stable one-byte tag, mixed into the signed message so a signature
+    /// cannot be replayed as valid under a different algorithm tag.
+    pub fn tag(&self) -> u8 {
+        match self {
+            SigAlg::Ed25519 => 0,
+            SigAlg::ES256 => 1,
+            SigAlg::ES256K => 2,
+        }
+    }
+}
+
+/// Credentials for authorizing a WorkPackage, including a server-issued
+/// anti-replay nonce (see `authorizer::new_nonce`).
+///
+/// `public_key`/`signature` are variable-length because their size depends
+/// on `alg` (32/64 bytes for Ed25519, 33/64 for the ECDSA algorithms), so a
+/// fixed-size `[u8; N]` can't represent all three. `nonce` is variable-length
+/// for the same reason a raw CSPRNG-issued token is: it's opaque bytes, not
+/// a counter, so there's no fixed width to commit to.
 #[derive(Encode, Decode, Clone, Debug, PartialEq)]
 pub struct AuthCredentials {
-    pub public_key: [u8; 32],
-    pub signature: [u8; 64],
-    pub nonce: u64,
+    pub alg: SigAlg,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub nonce: Vec<u8>,
+}
+
+impl AuthCredentials {
+    /// A fixed-size identifier for this credential's signer, in place of the
+    /// raw (variable-length, algorithm-dependent) public key:
+    /// `SHA-256(alg.tag() || public_key)`. [`Self::thumbprint`] presents it
+    /// as hex.
+    pub fn key_id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([self.alg.tag()]);
+        hasher.update(&self.public_key);
+        hasher.finalize().into()
+    }
+
+    /// A stable, hex-encoded handle for this credential's signer —
+    /// borrowed from ACME's "key thumbprint": the same `key_id` hash,
+    /// just presented as a string for logging/storage (e.g. in
+    /// `AuthRecord`) rather than used as a map key.
+    pub fn thumbprint(&self) -> String {
+        hex::encode(self.key_id())
+    }
+
+    /// The ACME-style "key authorization" challenge this credential's
+    /// signature must commit to: `payload_hash "." thumbprint "." nonce`,
+    /// each hex-encoded. Binding the signer's thumbprint (not just the raw
+    /// key) and the nonce into what's signed means a signature lifted from
+    /// one account/session/payload can never be replayed as valid for
+    /// another.
+    pub fn key_authorization(&self, payload_hash: &[u8; 32]) -> String {
+        format!(
+            "{}.{}.{}",
+            hex::encode(payload_hash),
+            self.thumbprint(),
+            hex::encode(&self.nonce),
+        )
+    }
+
+    /// The canonical message this credential's signature must cover:
+    /// `SHA-256(DOMAIN_TAG || alg.tag() || key_authorization)`.
+    pub fn signing_message(&self, payload_hash: &[u8; 32]) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(AUTH_DOMAIN_TAG);
+        hasher.update([self.alg.tag()]);
+        hasher.update(self.key_authorization(payload_hash).as_bytes());
+        hasher.finalize().to_vec()
+    }
+}
+
+/// A structured, decodable authorization-failure reason, in the spirit of
+/// ACME's "Problem" documents: callers `Decode` this instead of guessing at
+/// a bare SHA-256 digest of an error string, and get fields where the
+/// failure has more to say than its name.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub enum AuthProblem {
+    /// `AuthCredentials` didn't SCALE-decode from the auth parameter.
+    DecodeError,
+    /// `nonce` was not a currently-valid, unused nonce from `new_nonce`
+    /// (unknown, expired, or already consumed).
+    InvalidNonce { nonce: Vec<u8> },
+    /// The `WorkPackage` had no work items to authorize.
+    NoPayload,
+    /// `public_key` was the wrong length/format for `alg`.
+    InvalidPubKey,
+    /// The signature did not verify under `public_key` for the expected
+    /// message.
+    SignatureInvalid,
+    /// Verification succeeded but persisting the updated `AuthState` failed.
+    StateSaveError,
+}
+
+/// A privilege level granted to a public key in the `authorities` registry.
+///
+/// Ranked `Admin` > `Operator` > `ReadOnly`; use [`Role::satisfies`] rather
+/// than relying on derived ordering of the enum discriminants.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    Admin,
+    Operator,
+    ReadOnly,
+}
+
+impl Role {
+    fn rank(&self) -> u8 {
+        match self {
+            Role::Admin => 2,
+            Role::Operator => 1,
+            Role::ReadOnly => 0,
+        }
+    }
+
+    /// True if this role is at least as privileged as `min`.
+    pub fn satisfies(&self, min: Role) -> bool {
+        self.rank() >= min.rank()
+    }
+}
+
+/// Sliding anti-replay window for one signer, modeled on IPsec/ESP sequence
+/// number checking: `highest` is the largest nonce ever accepted, and
+/// `bitmap` tracks which of the 64 nonces below it have already been seen.
+/// This bounds per-key memory to 16 bytes while tolerating nonces that
+/// arrive slightly out of order.
+#[derive(Encode, Decode, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct ReplayWindow {
+    pub highest: u64,
+    pub bitmap: u64,
+}
+
+impl ReplayWindow {
+    /// True if `n` is new: either ahead of the window, or within the last 64
+    /// nonces and not yet marked seen. Does not mutate the window.
+    pub fn would_accept(&self, n: u64) -> bool {
+        if n > self.highest {
+            return true;
+        }
+        let age = self.highest - n;
+        age < 64 && (self.bitmap & (1u64 << age)) == 0
+    }
+
+    /// Record `n` as seen, advancing the window if `n` is the new highest.
+    ///
+    /// Callers should check [`Self::would_accept`] first; calling this on a
+    /// nonce that would not be accepted is a no-op beyond marking its bit.
+    pub fn accept(&mut self, n: u64) {
+        if n > self.highest {
+            let shift = n - self.highest;
+            self.bitmap = if shift >= 64 { 0 } else { self.bitmap << shift };
+            self.highest = n;
+        }
+        let age = self.highest - n;
+        if age < 64 {
+            self.bitmap |= 1u64 << age;
+        }
+    }
 }
 
 /// The persistent state of MyJamService, now including an admin and nonces.
@@ -18,8 +197,14 @@ pub struct AuthCredentials {
 pub struct ServiceState {
     pub counter: u64,
     pub last_payload_hash: [u8; 32],
-    pub admin: u64, // <-- ADD THIS LINE
-    pub nonces: BTreeMap<[u8; 32], u64>,
+    /// Multi-signer ACL: public key -> granted role. Empty until an
+    /// out-of-band bootstrap (e.g. config or `StateStore` seed) grants the
+    /// first `Admin`.
+    pub authorities: BTreeMap<[u8; 32], Role>,
+    pub nonces: BTreeMap<[u8; 32], ReplayWindow>,
+    /// Count of memos that decoded into `ServiceCommand::Unsupported`,
+    /// i.e. a command discriminant this deployment doesn't recognize.
+    pub rejected_commands: u64,
 }
 
 // Add this manual implementation of `Default`
@@ -28,15 +213,291 @@ impl Default for ServiceState {
         Self {
             counter: 0,
             last_payload_hash: [0; 32],
-            admin: 0, // Default admin to 0 (no admin)
+            authorities: BTreeMap::new(),
             nonces: BTreeMap::new(),
+            rejected_commands: 0,
         }
     }
 }
 
+impl ServiceState {
+    /// The role held by `key`, if any.
+    pub fn role_of(&self, key: &[u8; 32]) -> Option<Role> {
+        self.authorities.get(key).copied()
+    }
+
+    /// True if `key` holds a role at least as privileged as `min`.
+    pub fn authorize(&self, key: &[u8; 32], min: Role) -> bool {
+        self.role_of(key).is_some_and(|role| role.satisfies(min))
+    }
+}
+
 /// A command that can be sent to the service via the `on_transfer` memo field.
-#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+///
+/// The leading byte of the encoding is the command's capability tag (see
+/// [`ServiceCommand::supported_commands`]). `Decode` is implemented by hand
+/// rather than derived so a memo built against a newer service version that
+/// added a command this deployment doesn't know about decodes into
+/// `Unsupported` instead of aborting the whole transfer.
+#[derive(Encode, Clone, Debug, PartialEq)]
 pub enum ServiceCommand {
-    IncrementCounter { by: u64 },
-    ResetState,
+    IncrementCounter { caller: [u8; 32], by: u64 },
+    ResetState { caller: [u8; 32] },
+    GrantRole { caller: [u8; 32], key: [u8; 32], role: Role },
+    RevokeRole { caller: [u8; 32], key: [u8; 32] },
+    /// Run `commands` as a single atomic unit: either all apply or none do.
+    ///
+    /// `nonce` is consumed once for the whole batch (checked against
+    /// `ServiceState::nonces`), not per sub-command, so replay protection
+    /// stays coherent across a multi-command transfer.
+    Batch { caller: [u8; 32], nonce: u64, commands: Vec<ServiceCommand> },
+    /// A command whose capability tag this deployment doesn't recognize.
+    /// `raw` holds whatever bytes followed the tag, undecoded, so the memo
+    /// can still be logged or re-forwarded rather than silently dropped.
+    ///
+    /// On the wire, `raw` is length-prefixed (a plain `Vec<u8>` encoding)
+    /// rather than "whatever remains in the buffer": a command with an
+    /// unrecognized tag can appear anywhere a `ServiceCommand` can,
+    /// including as one element of `Batch`'s `commands`, and without a
+    /// length prefix there would be no way to tell where its payload ends
+    /// and the next sibling command begins. A producer that wants an old
+    /// deployment to gracefully skip a new command it doesn't understand
+    /// must emit that command's payload length-prefixed for this reason.
+    Unsupported { tag: u8, raw: Vec<u8> },
+}
+
+impl ServiceCommand {
+    const TAG_INCREMENT_COUNTER: u8 = 0;
+    const TAG_RESET_STATE: u8 = 1;
+    const TAG_GRANT_ROLE: u8 = 2;
+    const TAG_REVOKE_ROLE: u8 = 3;
+    const TAG_BATCH: u8 = 4;
+
+    /// Capability tags this build of the service understands. On-chain
+    /// callers can negotiate against this list before sending a command.
+    pub fn supported_commands() -> &'static [u8] {
+        &[
+            Self::TAG_INCREMENT_COUNTER,
+            Self::TAG_RESET_STATE,
+            Self::TAG_GRANT_ROLE,
+            Self::TAG_REVOKE_ROLE,
+            Self::TAG_BATCH,
+        ]
+    }
+}
+
+impl ServiceCommand {
+    /// Every `caller` field reachable from this command, including each
+    /// sub-command nested inside a `Batch`. A [`SignedServiceCommand`]
+    /// envelope must have every one of these equal to its own signer, so a
+    /// command that claims to act as a key it didn't sign for is rejected
+    /// before it ever reaches `apply_command`/`run_batch`.
+    pub fn callers(&self) -> Vec<[u8; 32]> {
+        match self {
+            ServiceCommand::IncrementCounter { caller, .. }
+            | ServiceCommand::ResetState { caller }
+            | ServiceCommand::GrantRole { caller, .. }
+            | ServiceCommand::RevokeRole { caller, .. } => alloc::vec![*caller],
+            ServiceCommand::Batch { caller, commands, .. } => {
+                let mut callers = alloc::vec![*caller];
+                for sub in commands {
+                    callers.extend(sub.callers());
+                }
+                callers
+            }
+            ServiceCommand::Unsupported { .. } => Vec::new(),
+        }
+    }
+}
+
+impl Decode for ServiceCommand {
+    fn decode<I: jam_codec::Input>(input: &mut I) -> Result<Self, jam_codec::Error> {
+        let tag = input.read_byte()?;
+        Ok(match tag {
+            Self::TAG_INCREMENT_COUNTER => ServiceCommand::IncrementCounter {
+                caller: Decode::decode(input)?,
+                by: Decode::decode(input)?,
+            },
+            Self::TAG_RESET_STATE => ServiceCommand::ResetState { caller: Decode::decode(input)? },
+            Self::TAG_GRANT_ROLE => ServiceCommand::GrantRole {
+                caller: Decode::decode(input)?,
+                key: Decode::decode(input)?,
+                role: Decode::decode(input)?,
+            },
+            Self::TAG_REVOKE_ROLE => ServiceCommand::RevokeRole {
+                caller: Decode::decode(input)?,
+                key: Decode::decode(input)?,
+            },
+            Self::TAG_BATCH => ServiceCommand::Batch {
+                caller: Decode::decode(input)?,
+                nonce: Decode::decode(input)?,
+                commands: Decode::decode(input)?,
+            },
+            unknown_tag => {
+                // Length-prefixed, not "read to EOF": this command may be
+                // one element of a `Batch`'s `commands`, and reading to EOF
+                // would swallow every sibling command's bytes that follow
+                // it in the same stream.
+                let raw = Vec::<u8>::decode(input)?;
+                ServiceCommand::Unsupported { tag: unknown_tag, raw }
+            }
+        })
+    }
+}
+
+/// Fixed prefix mixed into every [`SignedServiceCommand`]'s signed message,
+/// so a signature produced for a service command can never be replayed as a
+/// valid signature over an `AuthCredentials` (or vice versa) even if the two
+/// happened to share an encoding.
+pub const SERVICE_COMMAND_DOMAIN_TAG: &[u8] = b"jam_pvm.service_command.v1";
+
+/// Wire envelope `on_transfer`'s memo field actually decodes: a
+/// [`ServiceCommand`] together with a signature binding it to `public_key`.
+///
+/// Every `caller` field the command claims (see [`ServiceCommand::callers`])
+/// must equal this envelope's signer (see [`Self::key_id`]), and the
+/// signature itself must verify, before any command it carries is trusted
+/// to act as that caller — otherwise a decoded `caller` field is just a
+/// claim an attacker typed in, the same way an unauthenticated `AuthCredentials`
+/// would be.
+#[derive(Encode, Decode, Clone, Debug, PartialEq)]
+pub struct SignedServiceCommand {
+    pub alg: SigAlg,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+    pub command: ServiceCommand,
+}
+
+impl SignedServiceCommand {
+    /// A fixed-size identifier for this envelope's signer, computed the same
+    /// way as [`AuthCredentials::key_id`]: `SHA-256(alg.tag() || public_key)`.
+    /// `ServiceCommand::caller` fields are `[u8; 32]`, so comparing against
+    /// this (rather than the variable-length `public_key` itself) is what
+    /// lets a verified signature stand in for a `caller` field.
+    pub fn key_id(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update([self.alg.tag()]);
+        hasher.update(&self.public_key);
+        hasher.finalize().into()
+    }
+
+    /// The canonical message this envelope's signature must cover:
+    /// `SHA-256(SERVICE_COMMAND_DOMAIN_TAG || alg.tag() || command.encode())`.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut hasher = Sha256::new();
+        hasher.update(SERVICE_COMMAND_DOMAIN_TAG);
+        hasher.update([self.alg.tag()]);
+        hasher.update(self.command.encode());
+        hasher.finalize().to_vec()
+    }
+
+    /// True if every `caller` the wrapped command claims to act as is this
+    /// envelope's own signer. Checking this before verifying the signature
+    /// is just a cheap early-out; the signature check below is what actually
+    /// makes the claim trustworthy.
+    pub fn callers_match_signer(&self) -> bool {
+        let key_id = self.key_id();
+        self.command.callers().iter().all(|caller| *caller == key_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn replay_window_accepts_in_order_and_rejects_exact_replay() {
+        let mut window = ReplayWindow::default();
+        assert!(window.would_accept(0));
+        window.accept(0);
+        assert!(!window.would_accept(0), "replaying the same nonce must be rejected");
+        assert!(window.would_accept(1));
+        window.accept(1);
+        assert_eq!(window.highest, 1);
+    }
+
+    #[test]
+    fn replay_window_accepts_out_of_order_within_the_last_64() {
+        let mut window = ReplayWindow::default();
+        window.accept(5);
+        assert!(
+            window.would_accept(3),
+            "nonce 3 arriving after 5 but within the window should still be accepted once"
+        );
+        window.accept(3);
+        assert!(!window.would_accept(3), "nonce 3 must not be replayable after being accepted");
+        assert_eq!(window.highest, 5, "an out-of-order accept must not move the window backward");
+    }
+
+    #[test]
+    fn replay_window_boundary_at_64_nonces_behind_highest() {
+        let window = ReplayWindow { highest: 100, bitmap: 0 };
+        assert!(window.would_accept(37), "age 63 is still within the tracked window");
+        assert!(
+            !window.would_accept(36),
+            "age 64 is just outside the tracked window and must always be rejected"
+        );
+    }
+
+    #[test]
+    fn unknown_tag_sub_command_does_not_consume_following_bytes() {
+        // Two `ServiceCommand`s concatenated back-to-back, as they'd appear
+        // inside a decoded `Vec<ServiceCommand>`: first a command with an
+        // unrecognized tag and a length-prefixed payload, then a known one.
+        let mut bytes = Vec::new();
+        bytes.push(99u8); // a tag this deployment doesn't recognize
+        bytes.extend(vec![0xAAu8, 0xBB, 0xCC].encode()); // length-prefixed payload
+
+        let known = ServiceCommand::IncrementCounter { caller: [1u8; 32], by: 5 };
+        bytes.extend(known.encode());
+
+        let mut cursor = bytes.as_slice();
+        let first = ServiceCommand::decode(&mut cursor).expect("unknown-tag command should decode");
+        assert_eq!(first, ServiceCommand::Unsupported { tag: 99, raw: vec![0xAA, 0xBB, 0xCC] });
+
+        let second = ServiceCommand::decode(&mut cursor)
+            .expect("the sibling command after an unknown-tag command should still decode");
+        assert_eq!(second, known);
+    }
+
+    #[test]
+    fn batch_decodes_past_an_unknown_tag_sub_command() {
+        let known = ServiceCommand::IncrementCounter { caller: [2u8; 32], by: 9 };
+
+        let mut unknown_bytes = Vec::new();
+        unknown_bytes.push(77u8); // a tag this deployment doesn't recognize
+        unknown_bytes.extend(vec![0xDEu8, 0xAD].encode());
+
+        // SCALE's `Vec<T>` encoding is a compact-encoded length followed by
+        // each element's bytes; derive that length prefix from a throwaway
+        // two-element `Vec<u8>` rather than hand-rolling compact-int math,
+        // so this test can't drift from however the codec actually encodes
+        // "2" if that ever changes.
+        let length_prefix = {
+            let probe = vec![0u8, 0u8].encode();
+            probe[..probe.len() - 2].to_vec()
+        };
+
+        let mut commands_bytes = Vec::new();
+        commands_bytes.extend(length_prefix);
+        commands_bytes.extend(known.encode());
+        commands_bytes.extend(unknown_bytes);
+
+        let mut batch_bytes = Vec::new();
+        batch_bytes.push(ServiceCommand::TAG_BATCH);
+        batch_bytes.extend([3u8; 32].encode()); // caller
+        batch_bytes.extend(7u64.encode()); // nonce
+        batch_bytes.extend(commands_bytes);
+
+        let decoded = ServiceCommand::decode(&mut batch_bytes.as_slice()).expect("batch should decode");
+        match decoded {
+            ServiceCommand::Batch { commands, .. } => {
+                assert_eq!(commands.len(), 2, "the unknown-tag sub-command must not swallow its sibling");
+                assert_eq!(commands[0], known);
+                assert_eq!(commands[1], ServiceCommand::Unsupported { tag: 77, raw: vec![0xDE, 0xAD] });
+            }
+            other => panic!("expected Batch, got {:?}", other),
+        }
+    }
 }